@@ -6,10 +6,14 @@ import {
 import { getJWTObject } from '../utils/storage';
 import { createJwtHeader } from '../utils/jwtHeaders';
 import { getNodeUrl } from '../utils/node';
+import { sha256Hex } from '../utils/hash';
 import {
-  MessageMode,
+  MessageModeValue,
   EncryptedMessage,
   LegalDocument,
+  DocumentChunk,
+  DocumentSummary,
+  AccessLevel,
   LegalCase,
   LegalConsent,
   AIAnalysisResult,
@@ -17,10 +21,16 @@ import {
   CaseCreateParams,
   PrivacyLevel,
   CaseMember,
+  CaseRole,
+  CaseStatus,
 } from './clientApi';
-import { CipherEvent } from '@/types/events';
+import { CipherEvent, MessageSentPayload } from '@/types/events';
 
 
+// Must match MAX_CHUNK_SIZE in the contract: every chunk but the last is
+// exactly this long
+const UPLOAD_CHUNK_SIZE = 256 * 1024;
+
 export class CipherCircleApiClient implements CipherCircleApi {
   private rpcClient: JsonRpcClient;
   private wsClient: WsSubscriptionsClient;
@@ -37,11 +47,35 @@ export class CipherCircleApiClient implements CipherCircleApi {
   ): Promise<ApiResponse<void>> {
     throw new Error('Method not implemented.');
   }
-  processPayment(
-    doc_hash: string,
+  // Records a pending payment on the case; returns its payment_id
+  async processPayment(
+    case_id: string,
     amount: number,
   ): Promise<ApiResponse<string>> {
-    throw new Error('Method not implemented.');
+    const auth = this.getConfigAndJwt();
+    if ('error' in auth) return { error: auth.error! };
+    const { jwtObject, config } = auth;
+
+    const response = await this.rpcClient.query(
+      {
+        contextId: jwtObject?.context_id ?? '',
+        method: 'initiate_payment',
+        argsJson: { case_id, amount },
+        executorPublicKey: jwtObject.executor_public_key,
+      },
+      config,
+    );
+
+    if (response.error) {
+      return {
+        error: {
+          message: response.error.toString(),
+          code: response.error.code,
+        },
+      };
+    }
+    const result = response.result as any;
+    return { data: (result?.output ?? result) as string };
   }
   listCases(): Promise<ApiResponse<LegalCase[]>> {
     throw new Error('Method not implemented.');
@@ -69,22 +103,59 @@ export class CipherCircleApiClient implements CipherCircleApi {
   revokeConsent(lawyer_id: string): Promise<ApiResponse<void>> {
     throw new Error('Method not implemented.');
   }
-  requestAiAnalysis(
+  // Only ai_canister_id may post the result back via update_ai_analysis
+  async requestAiAnalysis(
     doc_hash: string,
     ai_canister_id: string,
-  ): Promise<ApiResponse<void>> {
-    throw new Error('Method not implemented.');
+  ): Promise<ApiResponse<string>> {
+    const auth = this.getConfigAndJwt();
+    if ('error' in auth) return { error: auth.error! };
+    const { jwtObject, config } = auth;
+
+    const response = await this.rpcClient.query(
+      {
+        contextId: jwtObject?.context_id ?? '',
+        method: 'request_ai_analysis',
+        argsJson: { doc_hash, ai_executor_id: ai_canister_id },
+        executorPublicKey: jwtObject.executor_public_key,
+      },
+      config,
+    );
+
+    if (response.error) {
+      return {
+        error: {
+          message: response.error.toString(),
+          code: response.error.code,
+        },
+      };
+    }
+    const result = response.result as any;
+    return { data: (result?.output ?? result) as string };
   }
-  updatePaymentStatus(
-    payment_id: string,
-    status: {
-      payment_id: string;
-      amount: number;
-      status: string;
-      timestamp: number;
-    },
-  ): Promise<ApiResponse<void>> {
-    throw new Error('Method not implemented.');
+  async completePayment(payment_id: string): Promise<ApiResponse<void>> {
+    const auth = this.getConfigAndJwt();
+    if ('error' in auth) return { error: auth.error! };
+    const { jwtObject, config } = auth;
+
+    const response = await this.rpcClient.query(
+      {
+        contextId: jwtObject?.context_id ?? '',
+        method: 'complete_payment',
+        argsJson: { payment_id },
+        executorPublicKey: jwtObject.executor_public_key,
+      },
+      config,
+    );
+
+    return response.error
+      ? {
+          error: {
+            message: response.error.toString(),
+            code: response.error.code,
+          },
+        }
+      : { data: undefined };
   }
 
   private getConfigAndJwt() {
@@ -117,7 +188,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'update_user_details',
-        argsJson: { details, caller_id:jwtObject.executor_public_key },
+        argsJson: { details },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -144,7 +215,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'grant_user_details_access',
-        argsJson:{ grantee_id, caller_id:jwtObject.executor_public_key },
+        argsJson: { grantee_id },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -193,7 +264,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
         {
           contextId: jwtObject?.context_id ?? '',
           method: 'get_accessible_user_details',
-          argsJson: { caller_id: jwtObject.executor_public_key },
+          argsJson: {},
           executorPublicKey: jwtObject.executor_public_key,
         },
         config,
@@ -218,7 +289,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
         {
           contextId: jwtObject?.context_id ?? '',
           method: 'request_user_details_access',
-          argsJson: { target_user_id , requester_id: jwtObject.executor_public_key },
+          argsJson: { target_user_id },
           executorPublicKey: jwtObject.executor_public_key,
         },
         config,
@@ -244,7 +315,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
         {
           contextId: jwtObject?.context_id ?? '',
           method: 'get_user_access_requests',
-          argsJson: { caller_id: jwtObject.executor_public_key },
+          argsJson: {},
           executorPublicKey: jwtObject.executor_public_key,
         },
         config,
@@ -272,12 +343,12 @@ export class CipherCircleApiClient implements CipherCircleApi {
         method: 'open_case',
         argsJson: {
           params: {
-            admin_id: jwtObject.executor_public_key,
             case_name: params.case_name,
             description: params.description,
             privacy_level: params.privacy_level,
             client_id: params.client_id,
             initial_docs: params.initial_docs || [],
+            invitation_policy: params.invitation_policy,
           },
         },
         executorPublicKey: jwtObject.executor_public_key,
@@ -304,7 +375,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'list_cases_for_user',
-        argsJson: { user_id: jwtObject.executor_public_key }, // Send user_id as parameter
+        argsJson: {},
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -320,6 +391,35 @@ export class CipherCircleApiClient implements CipherCircleApi {
       : { data: response.result as LegalCase[] };
   }
 
+  // Admin only; the contract rejects transitions it does not allow
+  async changeCaseStatus(
+    case_id: string,
+    status: CaseStatus,
+  ): Promise<ApiResponse<void>> {
+    const auth = this.getConfigAndJwt();
+    if ('error' in auth) return { error: auth.error! };
+    const { jwtObject, config } = auth;
+
+    const response = await this.rpcClient.query(
+      {
+        contextId: jwtObject?.context_id ?? '',
+        method: 'change_case_status',
+        argsJson: { case_id, status },
+        executorPublicKey: jwtObject.executor_public_key,
+      },
+      config,
+    );
+
+    return response.error
+      ? {
+          error: {
+            message: response.error.toString(),
+            code: response.error.code,
+          },
+        }
+      : { data: undefined };
+  }
+
   async listCaseMembers(case_id: string): Promise<ApiResponse<CaseMember[]>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
@@ -329,10 +429,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'list_case_members',
-        argsJson: {
-          caller_id: jwtObject.executor_public_key,
-          case_id,
-        },
+        argsJson: { case_id },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -351,7 +448,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
   async addCaseMember(
     case_id: string,
     new_member_id: string,
-    role: 'lawyer' | 'client',
+    role: CaseRole,
   ): Promise<ApiResponse<void>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
@@ -365,7 +462,6 @@ export class CipherCircleApiClient implements CipherCircleApi {
           case_id: case_id,
           new_member_id: new_member_id,
           role: role,
-          caller_id: jwtObject.executor_public_key,
         },
         executorPublicKey: jwtObject.executor_public_key,
       },
@@ -386,7 +482,9 @@ export class CipherCircleApiClient implements CipherCircleApi {
     case_id: string,
     ciphertext: number[],
     iv: number[],
-    mode: MessageMode,
+    mode: MessageModeValue,
+    reply_to?: string,
+    recipient_ids?: string[],
   ): Promise<ApiResponse<void>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
@@ -401,7 +499,8 @@ export class CipherCircleApiClient implements CipherCircleApi {
           ciphertext,
           iv,
           mode,
-          sender_id: jwtObject.executor_public_key,
+          reply_to,
+          recipient_ids,
         },
         executorPublicKey: jwtObject.executor_public_key,
       },
@@ -420,8 +519,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
 
   async markMessageRead(
     case_id: string,
-    message_index: number,
-    reader_id: string,
+    message_id: string,
   ): Promise<ApiResponse<void>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
@@ -431,7 +529,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'mark_message_read',
-        argsJson: { case_id, message_index, reader_id },
+        argsJson: { case_id, message_id },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -449,7 +547,6 @@ export class CipherCircleApiClient implements CipherCircleApi {
 
   async getVisibleMessages(
     case_id: string,
-    requester_id: string,
   ): Promise<ApiResponse<EncryptedMessage[]>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
@@ -459,7 +556,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'get_visible_messages',
-        argsJson: { case_id, requester_id },
+        argsJson: { case_id },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -486,7 +583,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'get_case_messages',
-        argsJson: { case_id, requester_id: jwtObject.executor_public_key },
+        argsJson: { case_id },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -504,7 +601,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
 
   async subscribeToCaseMessages(
     case_id: string,
-    callback: (msg: EncryptedMessage) => void,
+    callback: (sent: MessageSentPayload) => void,
     errorCallback?: (err: any) => void,
   ): Promise<void> {
     try {
@@ -521,8 +618,8 @@ export class CipherCircleApiClient implements CipherCircleApi {
         if (event.type === 'ExecutionEvent') {
           const data = event.data as unknown as CipherEvent;
           // Ensure the event is for the proper case.
-          if (data.payload.case_id === case_id) {
-            callback(data.payload.message);
+          if (data.type === 'MessageSent' && data.payload.case_id === case_id) {
+            callback(data.payload);
           }
         }
       });
@@ -535,48 +632,233 @@ export class CipherCircleApiClient implements CipherCircleApi {
     this.wsClient.disconnect();
   }
 
+  // Documents travel in UPLOAD_CHUNK_SIZE pieces: begin_upload declares the
+  // final hash and size and finalize_upload checks them. A failed upload is
+  // aborted so its chunks do not linger on the node. Resolves to doc_hash.
+  private async uploadInChunks(
+    content: Uint8Array,
+    doc_hash: string,
+    document_type: string,
+    case_id?: string,
+  ): Promise<ApiResponse<string>> {
+    const begun = await this.beginUpload(
+      doc_hash,
+      document_type,
+      content.length,
+      case_id,
+    );
+    if (begun.error) return { error: begun.error };
+    const upload_id = begun.data!;
+
+    for (let index = 0; index * UPLOAD_CHUNK_SIZE < content.length; index++) {
+      const offset = index * UPLOAD_CHUNK_SIZE;
+      const data = Array.from(
+        content.subarray(offset, offset + UPLOAD_CHUNK_SIZE),
+      );
+      const sent = await this.uploadChunk(upload_id, index, data);
+      if (sent.error) {
+        await this.abortUpload(upload_id);
+        return { error: sent.error };
+      }
+    }
+
+    const finalized = await this.finalizeUpload(upload_id);
+    if (finalized.error) await this.abortUpload(upload_id);
+    return finalized;
+  }
+
   async uploadDocumentToCase(
-    encrypted_content: number[],
+    content: Uint8Array,
     doc_hash: string,
     document_type: string,
     case_id: string,
-  ): Promise<ApiResponse<void>> {
+  ): Promise<ApiResponse<string>> {
+    return this.uploadInChunks(content, doc_hash, document_type, case_id);
+  }
+
+  async beginUpload(
+    doc_hash: string,
+    document_type: string,
+    size: number,
+    case_id?: string,
+  ): Promise<ApiResponse<string>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
     const { jwtObject, config } = auth;
 
-    // 1. Upload the document file "content" to the documents store.
-    const uploadResponse = await this.rpcClient.query(
+    const response = await this.rpcClient.query(
       {
         contextId: jwtObject?.context_id ?? '',
-        method: 'upload_document_case',
-        argsJson: {
-          encrypted_content, // file bytes as an array of numbers
-          doc_hash, // document id (includes file name and timestamp)
-          document_type, // MIME type, e.g. "image/png"
-          case_id, // associated case id
-          caller_id: jwtObject.executor_public_key,
+        method: 'begin_upload',
+        argsJson: { doc_hash, document_type, size, case_id },
+        executorPublicKey: jwtObject.executor_public_key,
+      },
+      config,
+    );
+
+    if (response.error) {
+      return {
+        error: {
+          message: response.error.toString(),
+          code: response.error.code,
         },
+      };
+    }
+    const result = response.result as any;
+    return { data: (result?.output ?? result) as string };
+  }
+
+  async uploadChunk(
+    upload_id: string,
+    index: number,
+    data: number[],
+  ): Promise<ApiResponse<string>> {
+    const auth = this.getConfigAndJwt();
+    if ('error' in auth) return { error: auth.error! };
+    const { jwtObject, config } = auth;
+
+    const response = await this.rpcClient.query(
+      {
+        contextId: jwtObject?.context_id ?? '',
+        method: 'upload_chunk',
+        argsJson: { upload_id, index, data },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
     );
 
-    if (uploadResponse.error) {
+    if (response.error) {
       return {
         error: {
-          message: uploadResponse.error.toString(),
-          code: uploadResponse.error.code,
+          message: response.error.toString(),
+          code: response.error.code,
         },
       };
     }
+    const result = response.result as any;
+    return { data: (result?.output ?? result) as string };
+  }
+
+  async finalizeUpload(
+    upload_id: string,
+  ): Promise<ApiResponse<string>> {
+    const auth = this.getConfigAndJwt();
+    if ('error' in auth) return { error: auth.error! };
+    const { jwtObject, config } = auth;
+
+    const response = await this.rpcClient.query(
+      {
+        contextId: jwtObject?.context_id ?? '',
+        method: 'finalize_upload',
+        argsJson: { upload_id },
+        executorPublicKey: jwtObject.executor_public_key,
+      },
+      config,
+    );
+
+    if (response.error) {
+      return {
+        error: {
+          message: response.error.toString(),
+          code: response.error.code,
+        },
+      };
+    }
+    const result = response.result as any;
+    return { data: (result?.output ?? result) as string };
+  }
+
+  async abortUpload(
+    upload_id: string,
+  ): Promise<ApiResponse<void>> {
+    const auth = this.getConfigAndJwt();
+    if ('error' in auth) return { error: auth.error! };
+    const { jwtObject, config } = auth;
 
+    const response = await this.rpcClient.query(
+      {
+        contextId: jwtObject?.context_id ?? '',
+        method: 'abort_upload',
+        argsJson: { upload_id },
+        executorPublicKey: jwtObject.executor_public_key,
+      },
+      config,
+    );
+
+    if (response.error) {
+      return {
+        error: {
+          message: response.error.toString(),
+          code: response.error.code,
+        },
+      };
+    }
     return { data: undefined };
   }
 
+  async getDocumentChunk(
+    doc_hash: string,
+    index: number,
+  ): Promise<ApiResponse<DocumentChunk>> {
+    const auth = this.getConfigAndJwt();
+    if ('error' in auth) return { error: auth.error! };
+    const { jwtObject, config } = auth;
+
+    const response = await this.rpcClient.query(
+      {
+        contextId: jwtObject?.context_id ?? '',
+        method: 'get_document_chunk',
+        argsJson: { doc_hash, index },
+        executorPublicKey: jwtObject.executor_public_key,
+      },
+      config,
+    );
+
+    if (response.error) {
+      return {
+        error: {
+          message: response.error.toString(),
+          code: response.error.code,
+        },
+      };
+    }
+    const result = response.result as any;
+    return { data: (result?.output ?? result) as DocumentChunk };
+  }
+
+  // Fetches a document chunk by chunk, checking every chunk and the
+  // assembled content against their SHA-256 hashes.
+  async downloadDocument(
+    doc: DocumentSummary,
+  ): Promise<ApiResponse<Uint8Array>> {
+    const content = new Uint8Array(doc.size);
+    let offset = 0;
+    for (let index = 0; index < doc.chunk_count; index++) {
+      const response = await this.getDocumentChunk(doc.document_hash, index);
+      if (response.error) return { error: response.error };
+
+      const chunk = response.data!;
+      const bytes = new Uint8Array(chunk.data);
+      if ((await sha256Hex(bytes.buffer)) !== chunk.chunk_hash) {
+        return {
+          error: { message: `Chunk ${index} failed integrity check`, code: 400 },
+        };
+      }
+      content.set(bytes, offset);
+      offset += bytes.length;
+    }
+
+    if ((await sha256Hex(content.buffer)) !== doc.document_hash) {
+      return {
+        error: { message: 'Document failed integrity check', code: 400 },
+      };
+    }
+    return { data: content };
+  }
+
   async listCaseDocuments(
     case_id: string,
-  ): Promise<ApiResponse<LegalDocument[]>> {
+  ): Promise<ApiResponse<DocumentSummary[]>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
     const { jwtObject, config } = auth;
@@ -585,7 +867,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'list_case_documents',
-        argsJson: { case_id, caller_id: jwtObject.executor_public_key },
+        argsJson: { case_id },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -598,13 +880,22 @@ export class CipherCircleApiClient implements CipherCircleApi {
             code: response.error.code,
           },
         }
-      : { data: response.result as LegalDocument[] };
+      : { data: response.result as DocumentSummary[] };
   }
 
   async storeDocumentInVault(
-    encrypted_content: number[],
+    content: Uint8Array,
     doc_hash: string,
     document_type: string,
+  ): Promise<ApiResponse<string>> {
+    return this.uploadInChunks(content, doc_hash, document_type);
+  }
+
+  async grantVaultAccess(
+    doc_hash: string,
+    grantee_id: string,
+    level: AccessLevel = 'read',
+    expires_at?: number,
   ): Promise<ApiResponse<void>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
@@ -613,12 +904,8 @@ export class CipherCircleApiClient implements CipherCircleApi {
     const response = await this.rpcClient.query(
       {
         contextId: jwtObject?.context_id ?? '',
-        method: 'store_document',
-        argsJson: {
-          encrypted_content,
-          doc_hash,
-          document_type,
-        },
+        method: 'grant_access',
+        argsJson: { doc_hash, grantee_id, level, expires_at },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -634,10 +921,9 @@ export class CipherCircleApiClient implements CipherCircleApi {
       : { data: undefined };
   }
 
-  async grantVaultAccess(
+  async revokeVaultAccess(
     doc_hash: string,
     grantee_id: string,
-    caller_id: string,
   ): Promise<ApiResponse<void>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
@@ -646,8 +932,8 @@ export class CipherCircleApiClient implements CipherCircleApi {
     const response = await this.rpcClient.query(
       {
         contextId: jwtObject?.context_id ?? '',
-        method: 'grant_access',
-        argsJson: { doc_hash, grantee_id, caller_id },
+        method: 'revoke_access',
+        argsJson: { doc_hash, grantee_id },
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -663,7 +949,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       : { data: undefined };
   }
 
-  async getAccessibleDocuments(): Promise<ApiResponse<LegalDocument[]>> {
+  async getAccessibleDocuments(): Promise<ApiResponse<DocumentSummary[]>> {
     const auth = this.getConfigAndJwt();
     if ('error' in auth) return { error: auth.error! };
     const { jwtObject, config } = auth;
@@ -672,7 +958,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
       {
         contextId: jwtObject?.context_id ?? '',
         method: 'get_accessible_documents',
-        argsJson: { caller_id: jwtObject.executor_public_key },
+        argsJson: {},
         executorPublicKey: jwtObject.executor_public_key,
       },
       config,
@@ -685,7 +971,7 @@ export class CipherCircleApiClient implements CipherCircleApi {
             code: response.error.code,
           },
         }
-      : { data: response.result as LegalDocument[] };
+      : { data: response.result as DocumentSummary[] };
   }
 
   // WebSocket Event Subscriptions
@@ -5,6 +5,10 @@ export enum MessageMode {
   Vanish = 'Vanish',
   Persistent = 'Persistent',
 }
+// Time-limited vanish mode; serialised by serde as { VanishAfter: { seconds } }
+export type TimedMessageMode = { VanishAfter: { seconds: number } };
+export type MessageModeValue = MessageMode | TimedMessageMode;
+
 export enum PrivacyLevel {
   Private = 'Private',
   Public = 'Public',
@@ -15,51 +19,193 @@ export enum PrivacyLevel {
   Public = 'Public', // Visible to whole organization
 }
 
+export enum CaseStatus {
+  Intake = 'Intake',
+  Active = 'Active',
+  OnHold = 'OnHold',
+  Closed = 'Closed',
+  Archived = 'Archived',
+}
+
+export type CaseRole =
+  | 'lawyer'
+  | 'client'
+  | 'paralegal'
+  | 'co_counsel'
+  | 'expert'
+  | 'opposing_counsel'
+  | 'observer';
+
+export enum InvitationPolicy {
+  AdminOnly = 'AdminOnly',
+  AnyLawyer = 'AnyLawyer',
+  AnyMemberWithApproval = 'AnyMemberWithApproval',
+}
+
+export interface PendingMember {
+  member_id: string;
+  role: CaseRole;
+  requested_by: string;
+  requested_at: number;
+}
+
+export enum InvitationStatus {
+  Pending = 'Pending',
+  Accepted = 'Accepted',
+  Declined = 'Declined',
+  Cancelled = 'Cancelled',
+}
+
+export interface CaseInvitation {
+  invitation_id: string;
+  case_id: string;
+  invitee_id: string;
+  role: CaseRole;
+  invited_by: string;
+  created_at: number;
+  expires_at: number;
+  status: InvitationStatus;
+}
+
 export interface CaseMember {
   member_id: string;
-  role: string; // "lawyer" or "client"
+  role: CaseRole;
   is_admin: boolean;
+  joined_at: number;
 }
 
 export interface CaseCreateParams {
-  admin_id: string; // mandatory
   case_name: string; // mandatory
   description: string; // mandatory
   privacy_level: PrivacyLevel; // mandatory
   client_id?: string; // optional
   initial_docs?: string[]; // optional
+  invitation_policy?: InvitationPolicy; // optional, defaults to AnyLawyer
+}
+
+export interface ReadReceipt {
+  reader_id: string;
+  read_at: number;
+}
+
+export interface RecipientStatus {
+  member_id: string;
+  delivered_at: number;
+  read_at?: number;
+}
+
+export interface MessageStatus {
+  message_id: string;
+  recipients: RecipientStatus[];
+}
+
+export interface MessageRevision {
+  content_hash: string;
+  replaced_at: number;
 }
 
 export interface EncryptedMessage {
+  message_id: string;
+  sequence: number;
   ciphertext: number[];
   iv: number[];
   sender_id: string;
   recipient_ids: string[];
   timestamp: number;
-  mode: MessageMode;
-  read_receipts: string[];
+  mode: MessageModeValue;
+  expires_at?: number;
+  reply_to?: string;
+  thread_root?: string;
+  read_receipts: ReadReceipt[];
+  revisions: MessageRevision[];
+  edited_at?: number;
+  retracted_at?: number;
+  updated_at: number;
+}
+
+// Pass `cursor` back as `before` (or `after` when paging forward)
+export interface MessagePage {
+  messages: EncryptedMessage[];
+  has_more: boolean;
+  cursor: number;
+}
+
+export type AccessLevel = 'read' | 'write' | 'share';
+
+export interface AccessGrant {
+  grantee_id: string;
+  level: AccessLevel;
+  granted_by: string;
+  granted_at: number;
+  expires_at?: number;
 }
 
 export interface LegalDocument {
-  encrypted_content: number[];
   document_hash: string;
   document_type: string;
+  size: number;
+  chunk_hashes: string[];
   owner_id: string;
   case_id?: string;
-  access_list: string[];
+  grants: AccessGrant[];
   ai_analysis_id?: string;
   payment_id?: string;
   timestamp: number;
+  version: number;
+  root_hash: string;
+  parent_hash?: string;
+  author_id: string;
+  change_note?: string;
+  superseded_by?: string;
   fileUrl?: string;
 }
 
+export type AnalysisStatus = 'not_requested' | 'pending' | 'completed';
+
+// Listing view of a document; content is fetched chunk by chunk
+export interface DocumentSummary {
+  document_hash: string;
+  document_type: string;
+  owner_id: string;
+  case_id?: string;
+  size: number;
+  chunk_count: number;
+  version: number;
+  timestamp: number;
+  grants: AccessGrant[]; // empty unless the caller owns the document
+  analysis_id?: string;
+  analysis_status: AnalysisStatus;
+  fileUrl?: string;
+}
+
+export interface DocumentChunk {
+  doc_hash: string;
+  index: number;
+  chunk_count: number;
+  chunk_hash: string;
+  data: number[];
+}
+
+export interface DocumentVersion {
+  doc_hash: string;
+  version: number;
+  parent_hash?: string;
+  author_id: string;
+  timestamp: number;
+  change_note?: string;
+}
+
 export interface LegalCase {
   case_id: string;
+  matter_number: string;
   case_name: string;
-  client_id: string;
-  lawyer_ids: string[];
-  admin_id: string;
-  status: string;
+  members: CaseMember[];
+  former_members: string[];
+  invitation_policy: InvitationPolicy;
+  pending_members: PendingMember[];
+  status: CaseStatus;
+  status_changed_by: string;
+  status_changed_at: number;
   related_documents: string[];
   privacy_level: PrivacyLevel;
 }
@@ -82,8 +228,20 @@ export interface AIAnalysisResult {
   recommendations: string[];
   generated_by: string;
   timestamp: number;
+  completed_at?: number;
 }
 
+// Mirrors the Rust `CipherError` enum; branch on `code` instead of message text
+export type CipherError =
+  | { code: 'NotFound'; data: { kind: string; id: string } }
+  | { code: 'Unauthorized'; data: { action: string } }
+  | { code: 'InvalidInput'; data: { field: string } }
+  | { code: 'Conflict'; data: { reason: string } }
+  | { code: 'Expired'; data: { what: string } }
+  | { code: 'ConsentRequired'; data: { scope: string } }
+  | { code: 'InvalidSignature' }
+  | { code: 'Storage'; data: string };
+
 // API Interface
 export interface CipherCircleApi {
   // Messaging
@@ -91,23 +249,22 @@ export interface CipherCircleApi {
     case_id: string,
     ciphertext: number[],
     iv: number[],
-    mode: MessageMode,
+    mode: MessageModeValue,
+    reply_to?: string,
+    recipient_ids?: string[],
   ): Promise<ApiResponse<void>>;
 
   markMessageRead(
     case_id: string,
-    message_index: number,
-    reader_id: string,
+    message_id: string,
   ): Promise<ApiResponse<void>>;
 
   getVisibleMessages(
     case_id: string,
-    requester_id: string,
   ): Promise<ApiResponse<EncryptedMessage[]>>;
 
   getCaseMessages(
     case_id: string,
-    requester_id: string,
   ): Promise<ApiResponse<EncryptedMessage[]>>;
 
   // Case Management
@@ -148,21 +305,13 @@ export interface CipherCircleApi {
   requestAiAnalysis(
     doc_hash: string,
     ai_canister_id: string,
-  ): Promise<ApiResponse<void>>;
+  ): Promise<ApiResponse<string>>;
 
-  // Payment Processing
+  // Payment Processing (needs manage_billing on the case)
   processPayment(
-    doc_hash: string,
+    case_id: string,
     amount: number,
   ): Promise<ApiResponse<string>>;
 
-  updatePaymentStatus(
-    payment_id: string,
-    status: {
-      payment_id: string;
-      amount: number;
-      status: string;
-      timestamp: number;
-    },
-  ): Promise<ApiResponse<void>>;
+  completePayment(payment_id: string): Promise<ApiResponse<void>>;
 }
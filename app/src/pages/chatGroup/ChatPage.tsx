@@ -5,9 +5,16 @@ import { EvervaultCard, Icon } from '@/components/ui/evervault-card';
 import { SidebarApp } from '@/components/sidebar/sidebarApp';
 import AddMemberModal from './AddMemberModal';
 import { CipherCircleApiClient } from '@/api/cipherCircleApi';
-import { CaseMember, LegalDocument, MessageMode } from '@/api/clientApi';
+import { MessageSentPayload } from '@/types/events';
+import {
+  CaseMember,
+  DocumentSummary,
+  MessageMode,
+  ReadReceipt,
+} from '@/api/clientApi';
 import { IconCopy } from '@tabler/icons-react';
 import { getJWTObject } from '@/utils/storage';
+import { sha256Hex } from '@/utils/hash';
 import { groupCollapsed } from 'console';
 import Tesseract from 'tesseract.js'; 
 import { ollamaActor } from '@/utils/actor';
@@ -22,12 +29,12 @@ interface EncryptedMessage {
   isAI?: boolean;
   text: string;
   sender_id: string;
-  read_receipts?: string[];
+  read_receipts?: ReadReceipt[];
 }
 
 export const ChatPage = () => {
   const { groupID } = useParams<{ groupID: string }>();
-  const [documents, setDocuments] = useState<LegalDocument[]>([]);
+  const [documents, setDocuments] = useState<DocumentSummary[]>([]);
   const [showAddMemberModal, setShowAddMemberModal] = useState(false);
   const [message, setMessage] = useState('');
   const [messages, setMessages] = useState<EncryptedMessage[]>([]);
@@ -81,10 +88,11 @@ export const ChatPage = () => {
   
     console.log('Subscribing to case messages for groupID:', groupID);
   
-    const handleNewMessage = (newMsg: EncryptedMessage) => {
-      console.log('Received new message:', newMsg);
-      setMessages((prev) => [...prev, newMsg]);
-      // Optionally, scroll to the bottom or process the message further.
+    // The event only announces the message; refetch so the contract decides
+    // whether this member may see it
+    const handleNewMessage = (sent: MessageSentPayload) => {
+      console.log('Received new message:', sent.message_id);
+      fetchMessages();
     };
   
     api.subscribeToCaseMessages(
@@ -158,13 +166,12 @@ export const ChatPage = () => {
 
         console.log('Messages:', messagesArray);
         // Process each message: decrypt and attach formatted time
-        const processed = messagesArray.map((msg: any, index: number) => ({
+        const processed = messagesArray.map((msg: any) => ({
           ...msg,
           text: decryptMessage(msg.ciphertext, msg.iv),
           // Adjust timestamp conversion as needed (here assuming nanoseconds)
           time: new Date(msg.timestamp / 1e6).toLocaleString(),
-          // Generate a unique key if not provided (here using timestamp plus index)
-          id: `${msg.timestamp}-${index}`,
+          id: msg.message_id,
         }));
         setMessages(processed);
       }
@@ -177,13 +184,12 @@ export const ChatPage = () => {
   };
 
   const getFileUrl = (
-    encryptedContent: number[],
+    encryptedContent: Uint8Array,
     documentType: string,
   ): string => {
     // Ensure encryptedContent is valid and non-empty
     if (!encryptedContent || encryptedContent.length === 0) return '';
-    const byteArray = new Uint8Array(encryptedContent);
-    const blob = new Blob([byteArray], { type: documentType });
+    const blob = new Blob([encryptedContent], { type: documentType });
     console.log('Blob:', blob);
     return URL.createObjectURL(blob);
   };
@@ -200,21 +206,31 @@ export const ChatPage = () => {
         setDocuments([]);
         return;
       }
-      const docsWithUrls = docs.map((doc) => ({
-        ...doc,
-        fileUrl:
-          doc.encrypted_content && doc.encrypted_content.length
-            ? getFileUrl(doc.encrypted_content, doc.document_type)
-            : '',
-      }));
-      console.log('docs with urls:', docsWithUrls);
-      setDocuments(docsWithUrls);
+      // Listings carry metadata only; content is fetched when a file is opened
+      setDocuments(docs);
     } catch (err) {
       setError('Failed to fetch documents');
       console.error(err);
     }
   };
 
+  // Streams a document chunk by chunk the first time it is opened
+  const openDocument = async (doc: DocumentSummary): Promise<string> => {
+    if (doc.fileUrl) return doc.fileUrl;
+    const content = await api.downloadDocument(doc);
+    if (content.error || !content.data) {
+      setError(content.error?.message || 'Failed to download document');
+      return '';
+    }
+    const fileUrl = getFileUrl(content.data, doc.document_type);
+    setDocuments((prev) =>
+      prev.map((d) =>
+        d.document_hash === doc.document_hash ? { ...d, fileUrl } : d,
+      ),
+    );
+    return fileUrl;
+  };
+
   const fetchMembers = async () => {
     if (!groupID) return;
     try {
@@ -320,14 +336,22 @@ export const ChatPage = () => {
     coffeeAmount: string,
   ) => {
     setShowPaymentModal(false);
+    if (!groupID) return;
     const requestTransferArg = {
       to: receiverAccountId,
       amount: Number(coffeeAmount),
     };
+    // The payment is recorded on the case first; only billing members may
+    const payment = await api.processPayment(groupID, Number(coffeeAmount));
+    if ('error' in payment && payment.error) {
+      setError(payment.error.message);
+      return;
+    }
     try {
       const { height } =
         await window.ic?.plug?.requestTransfer(requestTransferArg);
       console.log('Transfer successful. Block height:', height);
+      await api.completePayment(payment.data!);
     } catch (error) {
       console.error('Transfer failed:', error);
     }
@@ -344,14 +368,15 @@ export const ChatPage = () => {
   }, [messages]);
   useEffect(() => {
     if (messages.length > 0 && groupID && currentUserID) {
-      messages.forEach((msg, index) => {
+      messages.forEach((msg) => {
         // Only mark message as read if the current user is not already in the read_receipts array.
         if (
           !msg.read_receipts ||
-          (msg.read_receipts && !msg.read_receipts.includes(currentUserID))
+          (msg.read_receipts &&
+            !msg.read_receipts.some((r) => r.reader_id === currentUserID))
         ) {
           api
-            .markMessageRead(groupID, index, currentUserID)
+            .markMessageRead(groupID, msg.message_id)
             .then((response) => {
               if (response.error) {
                 console.error(
@@ -359,7 +384,7 @@ export const ChatPage = () => {
                   response.error.message,
                 );
               } else {
-                console.log(`Marked message ${index} as read`);
+                console.log(`Marked message ${msg.message_id} as read`);
               }
             })
             .catch((err) => console.error(err));
@@ -375,10 +400,10 @@ export const ChatPage = () => {
     try {
       // Convert file to a byte array
       const arrayBuffer = await file.arrayBuffer();
-      const fileBytes = Array.from(new Uint8Array(arrayBuffer));
+      const fileBytes = new Uint8Array(arrayBuffer);
 
-      // Use the file name and timestamp as a document identifier
-      const docId = `${file.name}_${Date.now()}`;
+      // Documents are keyed by the SHA-256 of their content
+      const docId = await sha256Hex(arrayBuffer);
 
       // Call the API to upload the document and associate it with the case
       const response = await api.uploadDocumentToCase(
@@ -653,23 +678,29 @@ export const ChatPage = () => {
                           className="max-w-xs object-contain cursor-pointer"
                           onClick={() => window.open(doc.fileUrl, '_blank')}
                         />
+                      ) : doc.fileUrl ? (
+                        <a
+                          href={doc.fileUrl}
+                          target="_blank"
+                          rel="noopener noreferrer"
+                          className="text-blue-600 underline"
+                        >
+                          View File
+                        </a>
                       ) : (
-                        doc.fileUrl && (
-                          <a
-                            href={doc.fileUrl}
-                            target="_blank"
-                            rel="noopener noreferrer"
-                            className="text-blue-600 underline"
-                          >
-                            View File
-                          </a>
-                        )
+                        <button
+                          className="text-left text-blue-600 underline"
+                          onClick={() => openDocument(doc)}
+                        >
+                          Open File
+                        </button>
                       )}
                       <button
                         className="px-4 py-2 bg-purple-500 text-white rounded hover:bg-purple-600 transition-colors"
-                        onClick={() => {
-                          if (doc.fileUrl) {
-                            handleAnalyzeFile(doc.fileUrl);
+                        onClick={async () => {
+                          const fileUrl = await openDocument(doc);
+                          if (fileUrl) {
+                            handleAnalyzeFile(fileUrl);
                           }
                         }}
                       >
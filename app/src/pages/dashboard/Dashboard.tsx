@@ -4,8 +4,9 @@ import { SidebarApp } from '@/components/sidebar/sidebarApp';
 import { EvervaultCard } from '@/components/ui/evervault-card';
 import CreateGroupModal from './createGroupModal';
 import { CipherCircleApiClient } from '@/api/cipherCircleApi';
-import { LegalCase } from '@/api/clientApi';
+import { CaseStatus, InvitationPolicy, LegalCase } from '@/api/clientApi';
 import { useNavigate } from 'react-router-dom';
+import { getJWTObject } from '@/utils/storage';
 
 const Dashboard = () => {
   const [showCreateModal, setShowCreateModal] = useState(false);
@@ -15,6 +16,7 @@ const Dashboard = () => {
 
   const api = new CipherCircleApiClient();
   const navigate = useNavigate();
+  const currentUserID = getJWTObject()?.executor_public_key || '';
 
   // Add useEffect to fetch groups on component mount
   useEffect(() => {
@@ -36,11 +38,20 @@ const Dashboard = () => {
         const formattedGroups = Array.isArray(cases)
           ? cases.map((group) => ({
               case_id: group.case_id || '',
+              matter_number: group.matter_number || '',
               case_name: group.case_name || '',
-              client_id: group.client_id || '',
-              lawyer_ids: Array.isArray(group.lawyer_ids) ? group.lawyer_ids : [],
-              admin_id: group.admin_id || '',
-              status: group.status || 'active',
+              members: Array.isArray(group.members) ? group.members : [],
+              former_members: Array.isArray(group.former_members)
+                ? group.former_members
+                : [],
+              invitation_policy:
+                group.invitation_policy || InvitationPolicy.AnyLawyer,
+              pending_members: Array.isArray(group.pending_members)
+                ? group.pending_members
+                : [],
+              status: group.status || CaseStatus.Intake,
+              status_changed_by: group.status_changed_by || '',
+              status_changed_at: group.status_changed_at || 0,
               related_documents: Array.isArray(group.related_documents) 
                 ? group.related_documents 
                 : [],
@@ -69,6 +80,17 @@ const Dashboard = () => {
     navigate(`/dashboard/chat-group/${groupId}`);
   };
 
+  // New cases start in intake until an admin activates them
+  const handleActivate = async (e: React.MouseEvent, groupId: string) => {
+    e.stopPropagation();
+    const response = await api.changeCaseStatus(groupId, CaseStatus.Active);
+    if ('error' in response && response.error) {
+      setError(response.error.message);
+      return;
+    }
+    fetchGroups();
+  };
+
   // Variants for parent container
   const containerVariants = {
     hidden: { opacity: 0 },
@@ -157,9 +179,21 @@ const Dashboard = () => {
                         </span>
                       </div>
                       
-                      <div className="flex justify-between text-neutral-600 dark:text-neutral-300 text-sm">
-                        <span>👥 {group.lawyer_ids.length} members</span>
-                        <span>Status: {group.status}</span>
+                      <div className="flex justify-between items-center text-neutral-600 dark:text-neutral-300 text-sm">
+                        <span>👥 {group.members.length} members</span>
+                        {group.status === CaseStatus.Intake &&
+                        group.members.some(
+                          (m) => m.member_id === currentUserID && m.is_admin,
+                        ) ? (
+                          <button
+                            onClick={(e) => handleActivate(e, group.case_id)}
+                            className="px-2 py-1 rounded-md text-xs bg-green-100 text-green-700 dark:bg-green-900/20 dark:text-green-400"
+                          >
+                            Activate
+                          </button>
+                        ) : (
+                          <span>Status: {group.status}</span>
+                        )}
                       </div>
                     </div>
                   </motion.div>
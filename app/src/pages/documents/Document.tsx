@@ -3,13 +3,14 @@ import { motion } from 'framer-motion';
 import { EvervaultCard, Icon } from '@/components/ui/evervault-card';
 import { SidebarApp } from '@/components/sidebar/sidebarApp';
 import { CipherCircleApiClient } from '@/api/cipherCircleApi';
-import { LegalDocument } from '@/api/clientApi';
+import { sha256Hex } from '@/utils/hash';
+import { DocumentSummary } from '@/api/clientApi';
 
 const Documents = () => {
-  const [documents, setDocuments] = useState<LegalDocument[]>([]);
+  const [documents, setDocuments] = useState<DocumentSummary[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
-  const [selectedDoc, setSelectedDoc] = useState<LegalDocument | null>(null);
+  const [selectedDoc, setSelectedDoc] = useState<DocumentSummary | null>(null);
   const [newAccess, setNewAccess] = useState('');
   const api = new CipherCircleApiClient();
 
@@ -17,12 +18,11 @@ const Documents = () => {
     fetchDocuments();
   }, []);
   const getFileUrl = (
-    encryptedContent: number[],
+    encryptedContent: Uint8Array,
     documentType: string,
   ): string => {
     if (!encryptedContent || encryptedContent.length === 0) return '';
-    const byteArray = new Uint8Array(encryptedContent);
-    const blob = new Blob([byteArray], { type: documentType });
+    const blob = new Blob([encryptedContent], { type: documentType });
     console.log('Blob:', blob);
     return URL.createObjectURL(blob);
   };
@@ -39,16 +39,8 @@ const Documents = () => {
         return;
       }
 
-      const docsWithUrls = docs.map((doc) => ({
-        ...doc,
-        fileUrl:
-          doc.encrypted_content && doc.encrypted_content.length > 0
-            ? getFileUrl(doc.encrypted_content, doc.document_type)
-            : '',
-      }));
-
-      console.log('docs with urls:', docsWithUrls);
-      setDocuments(docsWithUrls);
+      // Listings carry metadata only; content is fetched when a file is opened
+      setDocuments(docs);
     } catch (err) {
       setError('Failed to fetch documents');
       console.error(err);
@@ -57,14 +49,30 @@ const Documents = () => {
     }
   };
 
+  // Streams a document chunk by chunk the first time it is opened
+  const openDocument = async (doc: DocumentSummary) => {
+    if (doc.fileUrl) return;
+    const content = await api.downloadDocument(doc);
+    if (content.error || !content.data) {
+      setError(content.error?.message || 'Failed to download document');
+      return;
+    }
+    const fileUrl = getFileUrl(content.data, doc.document_type);
+    setDocuments((prev) =>
+      prev.map((d) =>
+        d.document_hash === doc.document_hash ? { ...d, fileUrl } : d,
+      ),
+    );
+  };
+
   const handleUploadDocument = async (file: File) => {
     try {
       // Convert file to byte array
       const arrayBuffer = await file.arrayBuffer();
-      const encrypted_content = Array.from(new Uint8Array(arrayBuffer));
+      const encrypted_content = new Uint8Array(arrayBuffer);
 
-      // Create hash from file name and timestamp
-      const doc_hash = `${file.name}_${Date.now()}`;
+      // Documents are keyed by the SHA-256 of their content
+      const doc_hash = await sha256Hex(arrayBuffer);
       const response = await api.storeDocumentInVault(
         encrypted_content,
         doc_hash,
@@ -88,7 +96,7 @@ const Documents = () => {
     isGroup: boolean,
   ) => {
     try {
-      const response = await api.grantVaultAccess(docHash, granteeId, isGroup);
+      const response = await api.grantVaultAccess(docHash, granteeId);
 
       if ('error' in response) {
         throw new Error(response.error.message);
@@ -104,6 +112,20 @@ const Documents = () => {
     }
   };
 
+  const handleRevokeAccess = async (docHash: string, granteeId: string) => {
+    try {
+      const response = await api.revokeVaultAccess(docHash, granteeId);
+
+      if ('error' in response) {
+        throw new Error(response.error.message);
+      }
+
+      await fetchDocuments();
+    } catch (err) {
+      setError('Failed to revoke access');
+    }
+  };
+
   return (
     <div className="flex min-h-screen bg-gradient-to-br from-white to-neutral-50 dark:from-neutral-900 dark:to-neutral-800">
       {/* Sidebar */}
@@ -202,19 +224,22 @@ const Documents = () => {
                       </a>
                     )
                   ) : (
-                    <p className="text-sm text-neutral-600 dark:text-neutral-400">
-                      No file available.
-                    </p>
+                    <button
+                      onClick={() => openDocument(doc)}
+                      className="text-blue-600 underline"
+                    >
+                      Open File
+                    </button>
                   )}
                 </div>
 
                 <div className="flex items-center justify-between">
                   <div className="flex -space-x-2">
-                    {doc.access_list.map((userId, index) => (
+                    {doc.grants.map((grant, index) => (
                       <div
                         key={index}
                         className="w-8 h-8 rounded-full bg-gradient-to-r from-blue-500 to-purple-500 border-2 border-white dark:border-neutral-900"
-                        title={userId}
+                        title={`${grant.grantee_id} (${grant.level})`}
                       />
                     ))}
                   </div>
@@ -293,15 +318,25 @@ const Documents = () => {
                   <h3 className="font-medium text-neutral-900 dark:text-white">
                     Current Access
                   </h3>
-                  {selectedDoc.access_list.map((userId, index) => (
+                  {selectedDoc.grants.map((grant, index) => (
                     <div
                       key={index}
                       className="flex items-center justify-between p-2 bg-neutral-100 dark:bg-neutral-700 rounded-lg"
                     >
                       <span className="text-neutral-900 dark:text-white">
-                        {userId}
+                        {grant.grantee_id} ({grant.level})
                       </span>
-                      {/* Add revoke functionality if needed */}
+                      <button
+                        onClick={() =>
+                          handleRevokeAccess(
+                            selectedDoc.document_hash,
+                            grant.grantee_id,
+                          )
+                        }
+                        className="text-sm text-red-500 hover:text-red-600"
+                      >
+                        Revoke
+                      </button>
                     </div>
                   ))}
                 </div>
@@ -1,12 +1,40 @@
 export type CipherEvent = {
     type: 'MessageSent';
-    payload: { case_id: string; sender: string , message: string };
+    // No content: fetch the message through the case read calls
+    payload: {
+      case_id: string;
+      sender: string;
+      message_id: string;
+      sequence: number;
+      reply_to?: string;
+      thread_root?: string;
+    };
   } | {
     type: 'DocumentUploaded'; 
     payload: { doc_hash: string; doc_type: string };
   } | {
-    type: 'AccessGranted';
+    type: 'DocumentVersioned';
+    payload: {
+      doc_hash: string;
+      root_hash: string;
+      parent_hash: string;
+      version: number;
+      author: string;
+    };
+  } | {
+    type: 'ConsentGranted';
     payload: { client_id: string; lawyer_id: string };
+  } | {
+    type: 'AccessGranted';
+    payload: {
+      doc_hash: string;
+      grantee_id: string;
+      level: string;
+      granted_by: string;
+    };
+  } | {
+    type: 'AccessRevoked';
+    payload: { doc_hash: string; grantee_id: string; revoked_by: string };
   } | {
     type: 'CaseOpened';
     payload: { case_id: string };
@@ -24,8 +52,48 @@ export type CipherEvent = {
     payload: { doc_hash: string; analysis_id: string };
   } | {
     type: 'PaymentInitiated';
-    payload: { doc_hash: string; payment_id: string };
+    payload: { case_id: string; payment_id: string; amount: number };
   } | {
     type: 'PaymentCompleted';
-    payload: { doc_hash: string; payment_id: string };
-  }
\ No newline at end of file
+    payload: { case_id: string; payment_id: string };
+  } | {
+    type: 'CaseStatusChanged';
+    payload: { case_id: string; from: string; to: string; changed_by: string };
+  } | {
+    type: 'MemberAdded';
+    payload: { case_id: string; member_id: string; role: string };
+  } | {
+    type: 'MemberApprovalRequested';
+    payload: { case_id: string; member_id: string; requested_by: string };
+  } | {
+    type: 'MemberApprovalRejected';
+    payload: { case_id: string; member_id: string };
+  } | {
+    type: 'MemberRemoved';
+    payload: { case_id: string; member_id: string; removed_by: string };
+  } | {
+    type: 'AdminTransferred';
+    payload: { case_id: string; from: string; to: string };
+  } | {
+    type: 'InvitationSent';
+    payload: { invitation_id: string; case_id: string; invitee_id: string; role: string };
+  } | {
+    type: 'InvitationAccepted';
+    payload: { invitation_id: string; case_id: string; invitee_id: string };
+  } | {
+    type: 'InvitationDeclined';
+    payload: { invitation_id: string; case_id: string; invitee_id: string };
+  } | {
+    type: 'InvitationCancelled';
+    payload: { invitation_id: string; case_id: string; invitee_id: string };
+  } | {
+    type: 'MessagesPurged';
+    payload: { case_id: string; count: number };
+  } | {
+    type: 'MessageEdited';
+    payload: { case_id: string; message_id: string; sender: string };
+  } | {
+    type: 'MessageRetracted';
+    payload: { case_id: string; message_id: string; sender: string };
+  }
+export type MessageSentPayload = Extract<CipherEvent, { type: 'MessageSent' }>['payload'];
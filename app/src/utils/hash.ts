@@ -0,0 +1,8 @@
+// Documents are keyed by the SHA-256 of their content; the contract
+// rejects uploads whose doc_hash does not match.
+export const sha256Hex = async (data: ArrayBuffer): Promise<string> => {
+  const digest = await crypto.subtle.digest('SHA-256', data);
+  return Array.from(new Uint8Array(digest))
+    .map((byte) => byte.toString(16).padStart(2, '0'))
+    .join('');
+};
@@ -1,15 +1,74 @@
-use calimero_sdk::{app, env,types::Error};
+use calimero_sdk::app;
 use calimero_sdk::borsh::{BorshDeserialize, BorshSerialize};
-use calimero_storage::collections::UnorderedMap;
+use calimero_storage::collections::{StoreError, UnorderedMap};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use ed25519_dalek::{Verifier, Signature, VerifyingKey as PublicKey}; 
 use std::convert::TryFrom;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+#[cfg(test)]
+use tests::{caller_id, time_now};
+
+// Resolves the authenticated caller from the executor public key
+#[cfg(not(test))]
+fn caller_id() -> String {
+    bs58::encode(calimero_sdk::env::executor_id()).into_string()
+}
+
+#[cfg(not(test))]
+fn time_now() -> u64 {
+    calimero_sdk::env::time_now()
+}
+
+// Unit tests run without a host to publish events to
+#[cfg(not(test))]
+macro_rules! emit {
+    ($event:expr) => { app::emit!($event) };
+}
+
+#[cfg(test)]
+macro_rules! emit {
+    ($event:expr) => { let _ = $event; };
+}
+
+// Error returned by every CipherState method, serialised as {"code", "data"}
+// so clients can branch on the code instead of matching message text.
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "code", content = "data")]
+pub enum CipherError {
+    #[error("{kind} not found: {id}")]
+    NotFound { kind: &'static str, id: String },
+    #[error("unauthorized: {action}")]
+    Unauthorized { action: &'static str },
+    #[error("invalid input: {field}")]
+    InvalidInput { field: &'static str },
+    #[error("conflict: {reason}")]
+    Conflict { reason: &'static str },
+    #[error("{what} expired")]
+    Expired { what: &'static str },
+    #[error("consent with {scope} scope required")]
+    ConsentRequired { scope: &'static str },
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("storage error: {0}")]
+    Storage(String)
+}
+
+impl From<StoreError> for CipherError {
+    fn from(err: StoreError) -> Self {
+        CipherError::Storage(err.to_string())
+    }
+}
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 pub enum MessageMode {
     Vanish,
-    Persistent
+    Persistent,
+    VanishAfter { seconds: u64 }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
@@ -19,6 +78,34 @@ pub enum PrivacyLevel {
     Public    
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum CaseStatus {
+    #[default]
+    Intake,
+    Active,
+    OnHold,
+    Closed,
+    Archived
+}
+
+impl CaseStatus {
+    pub fn can_transition_to(&self, next: CaseStatus) -> bool {
+        use CaseStatus::*;
+        matches!(
+            (self, next),
+            (Intake, Active) | (Intake, Closed)
+                | (Active, OnHold) | (Active, Closed)
+                | (OnHold, Active) | (OnHold, Closed)
+                | (Closed, Active) | (Closed, Archived)
+        )
+    }
+
+    // Closed and archived cases are read-only
+    pub fn accepts_activity(&self) -> bool {
+        !matches!(self, CaseStatus::Closed | CaseStatus::Archived)
+    }
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
 pub struct user_details {
     pub user_id: String,
@@ -29,66 +116,525 @@ pub struct user_details {
     pub access_list: Vec<String>,
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseRole {
+    Lawyer,
+    Client,
+    Paralegal,
+    CoCounsel,
+    Expert,
+    OpposingCounsel,
+    Observer
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CasePermission {
+    SendMessage,
+    ReadMessages,
+    UploadDocument,
+    ViewDocuments,
+    InviteMembers,
+    ManageBilling
+}
+
+impl CasePermission {
+    pub fn action(&self) -> &'static str {
+        match self {
+            CasePermission::SendMessage => "send_message",
+            CasePermission::ReadMessages => "read_messages",
+            CasePermission::UploadDocument => "upload_document",
+            CasePermission::ViewDocuments => "view_documents",
+            CasePermission::InviteMembers => "invite_members",
+            CasePermission::ManageBilling => "manage_billing"
+        }
+    }
+}
+
+impl CaseRole {
+    // Permission matrix; case admins are granted everything on top of this
+    pub fn allows(&self, permission: CasePermission) -> bool {
+        use CasePermission::*;
+        match self {
+            CaseRole::Lawyer => true,
+            CaseRole::CoCounsel => matches!(permission, SendMessage | ReadMessages | UploadDocument | ViewDocuments | InviteMembers),
+            CaseRole::Client => matches!(permission, SendMessage | ReadMessages | UploadDocument | ViewDocuments | ManageBilling),
+            CaseRole::Paralegal => matches!(permission, SendMessage | ReadMessages | UploadDocument | ViewDocuments),
+            CaseRole::Expert => matches!(permission, SendMessage | ReadMessages | ViewDocuments),
+            // The other side only sees documents explicitly granted to them
+            CaseRole::OpposingCounsel | CaseRole::Observer => false
+        }
+    }
+
+    pub fn is_counsel(&self) -> bool {
+        matches!(self, CaseRole::Lawyer | CaseRole::CoCounsel)
+    }
+}
+
+// Who may bring new members into a case
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum InvitationPolicy {
+    AdminOnly,
+    #[default]
+    AnyLawyer,
+    AnyMemberWithApproval
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct PendingMember {
+    member_id: String,
+    role: CaseRole,
+    requested_by: String,
+    requested_at: u64
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum InvitationStatus {
+    Pending,
+    Accepted,
+    Declined,
+    Cancelled
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct CaseInvitation {
+    invitation_id: String,
+    case_id: String,
+    invitee_id: String,
+    role: CaseRole,
+    invited_by: String,
+    created_at: u64,
+    expires_at: u64,
+    status: InvitationStatus
+}
+
+impl CaseInvitation {
+    pub fn is_open(&self) -> bool {
+        self.status == InvitationStatus::Pending && self.expires_at > time_now()
+    }
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 pub struct CaseMember {
     member_id: String,
-    role: String, // "lawyer" or "client"
-    is_admin: bool
+    role: CaseRole,
+    is_admin: bool,
+    joined_at: u64
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 pub struct CaseCreateParams {
-    admin_id: String,
     case_name: String,
     description: String,
     client_id: Option<String>,
     privacy_level: PrivacyLevel,
-    initial_docs: Option<Vec<String>>
+    initial_docs: Option<Vec<String>>,
+    invitation_policy: Option<InvitationPolicy>
 }
 
 // Legal Document Structure
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Default, Clone)]
 pub struct LegalDocument {
-    encrypted_content: Vec<u8>,
     document_hash: String,
     document_type: String,
+    size: u64,
+    chunk_hashes: Vec<String>,      // content in order; fetch with get_document_chunk
     owner_id: String,
     case_id: Option<String>,
-    access_list: Vec<String>,
+    grants: Vec<AccessGrant>,       // owner is implicit and holds every level
     ai_analysis_id: Option<String>,    
-    timestamp: u64
+    timestamp: u64,
+    version: u32,                   // 1 for the first upload of a document
+    root_hash: String,              // hash of version 1, identifies the chain
+    parent_hash: Option<String>,
+    author_id: String,
+    change_note: Option<String>,
+    superseded_by: Option<String>   // set once a newer version is uploaded
+}
+
+// Ordered so that each level includes the ones below it
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessLevel {
+    Read,
+    Write,
+    Share
+}
+
+// Who may open a document, at what level, on whose authority and until when
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AccessGrant {
+    grantee_id: String,
+    level: AccessLevel,
+    granted_by: String,
+    granted_at: u64,
+    expires_at: Option<u64>
+}
+
+impl AccessGrant {
+    pub fn is_live(&self) -> bool {
+        self.expires_at.map_or(true, |expires_at| expires_at > time_now())
+    }
+}
+
+// Entry in a document's revision history, without the content itself
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct DocumentVersion {
+    doc_hash: String,
+    version: u32,
+    parent_hash: Option<String>,
+    author_id: String,
+    timestamp: u64,
+    change_note: Option<String>
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisStatus {
+    NotRequested,
+    Pending,
+    Completed
+}
+
+// Listing view of a document: its metadata without the content, which is
+// fetched separately with get_document_content or get_document_chunk.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct DocumentSummary {
+    document_hash: String,
+    document_type: String,
+    owner_id: String,
+    case_id: Option<String>,
+    size: u64,
+    chunk_count: u32,
+    version: u32,
+    timestamp: u64,
+    grants: Vec<AccessGrant>,       // live grants, listed for the owner only
+    analysis_id: Option<String>,
+    analysis_status: AnalysisStatus
+}
+
+impl LegalDocument {
+    // Expired grants stay on record but are ignored by every read, as are
+    // grants delegated by someone who no longer holds share access: revoking
+    // or removing a delegator voids everything issued under them, and
+    // granting them access again does not bring those grants back.
+    fn live_grants(&self) -> impl Iterator<Item = &AccessGrant> {
+        self.grants.iter().filter(|grant| self.grant_is_live(grant, 0))
+    }
+
+    // depth bounds the walk up the delegation chain in case grants form a cycle
+    fn grant_is_live(&self, grant: &AccessGrant, depth: usize) -> bool {
+        grant.is_live()
+            && (grant.granted_by == self.owner_id
+                || (depth < self.grants.len()
+                    && self.grants.iter().any(|parent| {
+                        parent.grantee_id == grant.granted_by
+                            && parent.level >= AccessLevel::Share
+                            && parent.granted_at <= grant.granted_at
+                            && self.grant_is_live(parent, depth + 1)
+                    })))
+    }
+
+    fn has_access(&self, member_id: &str, level: AccessLevel) -> bool {
+        self.owner_id == member_id
+            || self.live_grants().any(|grant| grant.grantee_id == member_id && grant.level >= level)
+    }
+
+    fn version_info(&self) -> DocumentVersion {
+        DocumentVersion {
+            doc_hash: self.document_hash.clone(),
+            version: self.version,
+            parent_hash: self.parent_hash.clone(),
+            author_id: self.author_id.clone(),
+            timestamp: self.timestamp,
+            change_note: self.change_note.clone(),
+        }
+    }
+}
+
+// Content is stored in fixed-size chunks keyed by their SHA-256, so a large
+// document never has to travel through a single call.
+const MAX_CHUNK_SIZE: usize = 256 * 1024;
+const MAX_DOCUMENT_SIZE: u64 = 1 << 30;
+// An upload that receives nothing for this long expires (nanoseconds)
+const UPLOAD_IDLE_TIMEOUT: u64 = 60 * 60 * 1_000_000_000;
+
+fn chunk_count(size: u64) -> usize {
+    size.div_ceil(MAX_CHUNK_SIZE as u64) as usize
+}
+
+// One piece of a document; clients check SHA-256(data) == chunk_hash
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct DocumentChunk {
+    doc_hash: String,
+    index: u32,
+    chunk_count: u32,
+    chunk_hash: String,
+    data: Vec<u8>
+}
+
+// Chunked upload in progress. The document is only created by
+// finalize_upload, once the assembled content hashes to doc_hash.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct UploadSession {
+    upload_id: String,
+    uploader_id: String,
+    doc_hash: String,
+    document_type: String,
+    size: u64,
+    case_id: Option<String>,
+    parent_hash: Option<String>,
+    change_note: Option<String>,
+    chunk_hashes: Vec<Option<String>>,  // by chunk index, None until received
+    started_at: u64,
+    expires_at: u64                     // pushed back by every chunk
+}
+
+impl UploadSession {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at <= now
+    }
+}
+
+// Documents are content-addressed: the key must be the SHA-256 of the
+// ciphertext. Returns the canonical lowercase hex form to store under.
+fn verify_content_hash(encrypted_content: &[u8], doc_hash: &str) -> Result<String, CipherError> {
+    let computed = hex::encode(Sha256::digest(encrypted_content));
+    if !computed.eq_ignore_ascii_case(doc_hash) {
+        return Err(CipherError::InvalidInput { field: "doc_hash" });
+    }
+    Ok(computed)
 }
 
 // Secure Message Structure
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 pub struct EncryptedMessage {
+    message_id: String,
+    sequence: u64,
     ciphertext: Vec<u8>,
     iv: Vec<u8>,
     sender_id: String,
     recipient_ids: Vec<String>,
     timestamp: u64,
     mode: MessageMode, 
-    read_receipts: Vec<String>
+    expires_at: Option<u64>,
+    reply_to: Option<String>,
+    thread_root: Option<String>,
+    read_receipts: Vec<ReadReceipt>,
+    revisions: Vec<MessageRevision>,
+    edited_at: Option<u64>,
+    retracted_at: Option<u64>,
+    updated_at: u64                 // send time, then time of the last edit or retraction
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct ReadReceipt {
+    reader_id: String,
+    read_at: u64
+}
+
+// Delivery and read state of a message for one recipient
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct RecipientStatus {
+    member_id: String,
+    delivered_at: u64,
+    read_at: Option<u64>
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct MessageStatus {
+    message_id: String,
+    recipients: Vec<RecipientStatus>
+}
+
+// Hash-chained record of content that was replaced by an edit or retraction
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct MessageRevision {
+    content_hash: String,
+    replaced_at: u64
+}
+
+impl EncryptedMessage {
+    // Content hash over case, sender, sequence, time and payload; stable
+    // across deletions. A sender never holds two messages at one sequence,
+    // so resending the same payload in the same tick still gets a new ID.
+    pub fn compute_id(&self, case_id: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(case_id.as_bytes());
+        hasher.update(self.sender_id.as_bytes());
+        hasher.update(self.sequence.to_le_bytes());
+        hasher.update(self.timestamp.to_le_bytes());
+        hasher.update(&self.iv);
+        hasher.update(&self.ciphertext);
+        hex::encode(&hasher.finalize()[..16])
+    }
+
+    // Chains the current content onto the previous revision hash, so dropping
+    // or reordering revisions breaks every later hash.
+    fn push_revision(&mut self, now: u64) {
+        let mut hasher = Sha256::new();
+        if let Some(previous) = self.revisions.last() {
+            hasher.update(previous.content_hash.as_bytes());
+        }
+        hasher.update(&self.iv);
+        hasher.update(&self.ciphertext);
+        self.revisions.push(MessageRevision {
+            content_hash: hex::encode(hasher.finalize()),
+            replaced_at: now
+        });
+    }
+
+    pub fn read_at(&self, member_id: &str) -> Option<u64> {
+        self.read_receipts.iter()
+            .find(|receipt| receipt.reader_id == member_id)
+            .map(|receipt| receipt.read_at)
+    }
+
+    // Everyone the message was sent to, including vanish readers who have
+    // since dropped out of recipient_ids
+    pub fn audience(&self) -> Vec<String> {
+        let mut audience = self.recipient_ids.clone();
+        for receipt in &self.read_receipts {
+            if !audience.contains(&receipt.reader_id) {
+                audience.push(receipt.reader_id.clone());
+            }
+        }
+        audience
+    }
+
+    pub fn is_addressed_to(&self, member_id: &str) -> bool {
+        self.sender_id == member_id || self.recipient_ids.iter().any(|id| id == member_id)
+    }
+
+    pub fn is_unread_by(&self, member: &CaseMember) -> bool {
+        self.sender_id != member.member_id
+            && self.retracted_at.is_none()
+            && self.visible_to(member)
+            && self.read_at(&member.member_id).is_none()
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.map_or(false, |expires_at| expires_at <= now)
+    }
+
+    // Recipients only see what was sent after they joined, and a vanish
+    // message disappears for a reader once they have read it.
+    pub fn visible_to(&self, member: &CaseMember) -> bool {
+        self.timestamp >= member.joined_at
+            && self.recipient_ids.contains(&member.member_id)
+            && !(matches!(self.mode, MessageMode::Vanish) && self.read_at(&member.member_id).is_some())
+    }
+}
+
+// One page of case messages, oldest first. Pass `cursor` back as `before`
+// (or `after`, when paging forward) to fetch the next page.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+pub struct MessagePage {
+    messages: Vec<EncryptedMessage>,
+    has_more: bool,
+    cursor: u64
 }
 
 // Legal Case Structure
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Default, Clone)]
 pub struct LegalCase {
     case_id: String,
+    matter_number: String,
     case_name: String,
-    client_id: String,
-    lawyer_ids: Vec<String>,
-    admin_id: String,  // New field for admin
-    status: String,
+    members: Vec<CaseMember>,
+    former_members: Vec<String>,    // their messages stay in the case history
+    invitation_policy: InvitationPolicy,
+    pending_members: Vec<PendingMember>,
+    status: CaseStatus,
+    status_changed_by: String,
+    status_changed_at: u64,
     related_documents: Vec<String>,
     privacy_level: PrivacyLevel
 }
 
 impl LegalCase {
     pub fn get_participants(&self) -> Vec<String> {
-        let mut participants = vec![self.client_id.clone()];
-        participants.extend(self.lawyer_ids.clone());
-        participants
+        self.members.iter().map(|m| m.member_id.clone()).collect()
+    }
+
+    // Members who receive case chat; the default audience of a message
+    pub fn message_readers(&self) -> Vec<String> {
+        self.members.iter()
+            .filter(|m| m.is_admin || m.role.allows(CasePermission::ReadMessages))
+            .map(|m| m.member_id.clone())
+            .collect()
+    }
+
+    // Everyone who may have written to the case chat, past members included
+    pub fn message_senders(&self) -> impl Iterator<Item = &str> {
+        self.members.iter()
+            .map(|m| m.member_id.as_str())
+            .chain(self.former_members.iter().map(String::as_str).filter(|id| !self.is_member(id)))
+    }
+
+    pub fn member(&self, member_id: &str) -> Option<&CaseMember> {
+        self.members.iter().find(|m| m.member_id == member_id)
+    }
+
+    pub fn is_member(&self, member_id: &str) -> bool {
+        self.member(member_id).is_some()
+    }
+
+    pub fn is_admin(&self, member_id: &str) -> bool {
+        self.member(member_id).map_or(false, |m| m.is_admin)
+    }
+
+    pub fn client_id(&self) -> Option<&str> {
+        self.members.iter()
+            .find(|m| m.role == CaseRole::Client)
+            .map(|m| m.member_id.as_str())
+    }
+
+    // Counsel handle the client's material on the client's consent; returns
+    // the client whose consent member_id needs on this case, if any.
+    pub fn consent_client_for(&self, member_id: &str) -> Option<&str> {
+        let client_id = self.client_id()?;
+        let counsel = self.member(member_id).map_or(false, |m| m.role.is_counsel());
+        (counsel && client_id != member_id).then_some(client_id)
+    }
+
+    pub fn admin_count(&self) -> usize {
+        self.members.iter().filter(|m| m.is_admin).count()
+    }
+
+    pub fn check_new_member(&self, member_id: &str, role: CaseRole) -> Result<(), CipherError> {
+        if member_id.is_empty() {
+            return Err(CipherError::InvalidInput { field: "new_member_id" });
+        }
+        if self.is_member(member_id) {
+            return Err(CipherError::Conflict { reason: "already a case member" });
+        }
+        if role == CaseRole::Client && self.client_id().is_some() {
+            return Err(CipherError::Conflict { reason: "case already has a client" });
+        }
+        Ok(())
+    }
+
+    // Applies the invitation policy: Ok(true) when the caller may add members
+    // directly, Ok(false) when the addition has to wait for admin approval.
+    pub fn check_invitation_policy(&self, caller: &str) -> Result<bool, CipherError> {
+        if self.is_admin(caller) {
+            return Ok(true);
+        }
+        match self.invitation_policy {
+            InvitationPolicy::AdminOnly => Err(CipherError::Unauthorized { action: "add_case_member" }),
+            InvitationPolicy::AnyLawyer => self.require(caller, CasePermission::InviteMembers).map(|_| true),
+            InvitationPolicy::AnyMemberWithApproval if self.is_member(caller) => Ok(false),
+            InvitationPolicy::AnyMemberWithApproval => Err(CipherError::Unauthorized { action: "add_case_member" })
+        }
+    }
+
+    // Central permission check used by every case operation
+    pub fn require(&self, member_id: &str, permission: CasePermission) -> Result<&CaseMember, CipherError> {
+        match self.member(member_id) {
+            Some(m) if m.is_admin || m.role.allows(permission) => Ok(m),
+            _ => Err(CipherError::Unauthorized { action: permission.action() })
+        }
     }
 }
 
@@ -103,12 +649,58 @@ pub struct LegalConsent {
     signed_message: Vec<u8>, // Signed consent details
     signature: Vec<u8> // Cryptographic signature
 }
+
+// Consent scopes are cumulative: share implies write, write implies read
+fn scope_rank(scope: &str) -> Option<u8> {
+    match scope {
+        "read" => Some(1),
+        "write" => Some(2),
+        "share" => Some(3),
+        _ => None
+    }
+}
+
+impl LegalConsent {
+    pub fn is_live(&self) -> bool {
+        self.expiration > time_now()
+    }
+
+    pub fn covers(&self, operation: &str) -> bool {
+        match (scope_rank(&self.scope), scope_rank(operation)) {
+            (Some(granted), Some(required)) => granted >= required,
+            _ => false
+        }
+    }
+
+    // Canonical payload the client signs: client:lawyer:scope:expiration
+    pub fn canonical_message(&self) -> Vec<u8> {
+        format!("{}:{}:{}:{}", self.client_id, self.lawyer_id, self.scope, self.expiration).into_bytes()
+    }
+
+    pub fn verify_signature(&self) -> Result<(), CipherError> {
+        if self.signed_message != self.canonical_message() {
+            return Err(CipherError::InvalidInput { field: "signed_message" });
+        }
+
+        let key_bytes = <[u8; 32]>::try_from(self.public_key.as_slice())
+            .map_err(|_| CipherError::InvalidInput { field: "public_key" })?;
+        let public_key = PublicKey::from_bytes(&key_bytes)
+            .map_err(|_| CipherError::InvalidInput { field: "public_key" })?;
+        let signature = Signature::from_slice(&self.signature)
+            .map_err(|_| CipherError::InvalidInput { field: "signature" })?;
+
+        public_key.verify(&self.signed_message, &signature)
+            .map_err(|_| CipherError::InvalidSignature)
+    }
+}
 // Add new payment status enum
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Default, Clone)]
 pub struct PaymentStatus {
     payment_id: String,
+    case_id: String,
+    initiated_by: String,
     amount: u64,
-    status: String,
+    status: String,     // pending, then completed
     timestamp: u64
 }
  
@@ -129,133 +721,678 @@ pub struct AIAnalysisResult {
     risks_detected: u8,
     recommendations: Vec<String>,
     generated_by: String, // ICP canister ID
-    timestamp: u64
+    timestamp: u64,
+    completed_at: Option<u64>   // set once the canister posts its result
 }
 
 #[app::event]
 pub enum CipherEvent<'a> {
-    MessageSent { case_id: &'a str, sender: &'a str , message: &'a EncryptedMessage },
+    MessageSent { case_id: &'a str, sender: &'a str, message_id: &'a str, sequence: u64, reply_to: Option<&'a str>, thread_root: Option<&'a str> },
     DocumentUploaded { doc_hash: &'a str, doc_type: &'a str },
-    AccessGranted { client_id: &'a str, lawyer_id: &'a str },
+    DocumentVersioned { doc_hash: &'a str, root_hash: &'a str, parent_hash: &'a str, version: u32, author: &'a str },
+    ConsentGranted { client_id: &'a str, lawyer_id: &'a str },
+    AccessGranted { doc_hash: &'a str, grantee_id: &'a str, level: &'a AccessLevel, granted_by: &'a str },
+    AccessRevoked { doc_hash: &'a str, grantee_id: &'a str, revoked_by: &'a str },
     CaseOpened { case_id: &'a str },
     DocumentAnalyzed { doc_hash: &'a str },
     ConsentRevoked { client_id: &'a str, lawyer_id: &'a str },
     AnalysisRequested { doc_hash: &'a str, ai_canister: &'a str },
     AnalysisCompleted { doc_hash: &'a str, analysis_id: &'a str },
-    PaymentInitiated { doc_hash: &'a str, payment_id: &'a str },
-    PaymentCompleted { doc_hash: &'a str, payment_id: &'a str },
-    MemberAdded { case_id: &'a str, member_id: &'a str, role: &'a str },
+    PaymentInitiated { case_id: &'a str, payment_id: &'a str, amount: u64 },
+    PaymentCompleted { case_id: &'a str, payment_id: &'a str },
+    MemberAdded { case_id: &'a str, member_id: &'a str, role: &'a CaseRole },
+    MemberApprovalRequested { case_id: &'a str, member_id: &'a str, requested_by: &'a str },
+    MemberApprovalRejected { case_id: &'a str, member_id: &'a str },
+    InvitationSent { invitation_id: &'a str, case_id: &'a str, invitee_id: &'a str, role: &'a CaseRole },
+    InvitationAccepted { invitation_id: &'a str, case_id: &'a str, invitee_id: &'a str },
+    InvitationDeclined { invitation_id: &'a str, case_id: &'a str, invitee_id: &'a str },
+    InvitationCancelled { invitation_id: &'a str, case_id: &'a str, invitee_id: &'a str },
+    CaseStatusChanged { case_id: &'a str, from: &'a CaseStatus, to: &'a CaseStatus, changed_by: &'a str },
+    MessagesPurged { case_id: &'a str, count: u64 },
+    MessageEdited { case_id: &'a str, message_id: &'a str, sender: &'a str },
+    MessageRetracted { case_id: &'a str, message_id: &'a str, sender: &'a str },
+    MemberRemoved { case_id: &'a str, member_id: &'a str, removed_by: &'a str },
+    AdminTransferred { case_id: &'a str, from: &'a str, to: &'a str },
 }
 
 #[app::state(emits = for<'a> CipherEvent<'a>)]
 #[derive(Default, BorshSerialize, BorshDeserialize)]
 #[borsh(crate = "calimero_sdk::borsh")]
 pub struct CipherState {
-    messages: UnorderedMap<String, Vec<EncryptedMessage>>, // case_id -> messages
+    messages: UnorderedMap<String, EncryptedMessage>,      // case_id:message_id -> message
+    message_index: UnorderedMap<String, String>,           // case_id:sequence:sender_id -> message_id (append-only)
+    message_counts: UnorderedMap<String, u64>,             // case_id -> next sequence
+    message_changes: UnorderedMap<String, String>,         // case_id:change:sender_id -> edited or retracted message_id
+    change_counts: UnorderedMap<String, u64>,              // case_id -> next change
     documents: UnorderedMap<String, LegalDocument>,        // doc_hash -> document
+    document_versions: UnorderedMap<String, Vec<String>>,  // root_hash -> version hashes, oldest first
+    chunks: UnorderedMap<String, Vec<u8>>,                 // chunk_hash -> bytes, shared across documents
+    uploads: UnorderedMap<String, UploadSession>,          // upload_id -> chunked upload in progress
     cases: UnorderedMap<String, LegalCase>,               // case_id -> case
     consents: UnorderedMap<String, LegalConsent>,         // client_id:lawyer_id -> consent
     ai_results: UnorderedMap<String, AIAnalysisResult>,   // analysis_id -> result
-    payments: UnorderedMap<String, PaymentStatus>,        // payment_id -> status
+    payments: UnorderedMap<String, PaymentStatus>,        // payment_id -> payment
     user_details: UnorderedMap<String, user_details>,          // client_id -> details
     access_requests: UnorderedMap<String, Vec<String>>,    // doc_hash -> requestors
+    invitations: UnorderedMap<String, CaseInvitation>,    // case_id:invitee_id -> invitation
+    matter_sequences: UnorderedMap<String, u64>,          // creator_id -> cases opened (single writer each)
+    sender_logs: UnorderedMap<String, String>,            // case_id:sender_id:n -> sender's nth message_id (single writer each)
+    sender_counts: UnorderedMap<String, u64>,             // case_id:sender_id -> messages sent
+    read_marks: UnorderedMap<String, u64>,                // case_id:member_id:sender_id -> first entry of the sender's log that may be unread
 }
 
-#[app::logic]
-impl CipherState {
-    #[app::init]
-    pub fn init() -> Self {
-        Self {
-            messages: UnorderedMap::new(),
-            documents: UnorderedMap::new(),
-            cases: UnorderedMap::new(),
-            consents: UnorderedMap::new(),
-            ai_results: UnorderedMap::new(),
-            payments: UnorderedMap::new(),
-            user_details: UnorderedMap::new(),
-            access_requests: UnorderedMap::new()
-        }
-    }   
-    //User Details Access Control 
-    pub fn update_user_details(
-        &mut self,
-        details: user_details,
-        caller_id: String
-    ) -> Result<(), Error> {
-        let caller = caller_id.clone();
-
-        // Prevent updating someone else’s details.
-        if caller != details.user_id {
-            return Err(Error::msg("Unauthorized: can only update your own details"));
-        }
-        
-    
-        let mut current = self.user_details.get(&caller)?.unwrap_or(user_details{
-            user_id: caller.clone(),
-            name: None,
-            phone: None,
-            email: None,
-            wallet_id: None,
-            access_list: Vec::new(),
-        });
+const MAX_MESSAGE_PAGE: usize = 100;
 
+fn message_key(case_id: &str, message_id: &str) -> String {
+    format!("{}:{}", case_id, message_id)
+}
 
-        if details.name.is_some() {
-            current.name = details.name;
-        }
-        if details.phone.is_some() {
-            current.phone = details.phone;
-        }
-        if details.email.is_some() {
-            current.email = details.email;
-        }
-        if !details.access_list.is_empty() {
-            current.access_list = details.access_list;
-        }
+fn message_index_key(case_id: &str, sequence: u64, sender_id: &str) -> String {
+    format!("{}:{}:{}", case_id, sequence, sender_id)
+}
 
-        self.user_details.insert(caller, current)?;
-        Ok(())
-    }
-    pub fn request_user_details_access(
-        &mut self,
-        target_user_id: String,
-        requester_id: String,
-    ) -> Result<(), Error> {
- 
-        if target_user_id == requester_id {
-            return Err(Error::msg("Cannot request access to your own details"));
-        }
+fn sender_count_key(case_id: &str, sender_id: &str) -> String {
+    format!("{}:{}", case_id, sender_id)
+}
 
+fn sender_log_key(case_id: &str, sender_id: &str, entry: u64) -> String {
+    format!("{}:{}:{}", case_id, sender_id, entry)
+}
 
-        let mut requests = self.access_requests.get(&target_user_id)?.unwrap_or_default();
+fn read_mark_key(case_id: &str, member_id: &str, sender_id: &str) -> String {
+    format!("{}:{}:{}", case_id, member_id, sender_id)
+}
 
-        if !requests.contains(&requester_id) {
-            requests.push(requester_id);
+impl CipherState {
+    // Messages are stored one entry each, so sending or marking a message
+    // touches a constant number of keys regardless of history length.
+    // Nodes sending concurrently may draw the same sequence; the sender in
+    // the index key keeps both messages, and readers order ties by sender.
+    fn append_message(&mut self, case_id: &str, message: &mut EncryptedMessage) -> Result<(), CipherError> {
+        let mut sequence = self.message_counts.get(case_id)?.unwrap_or_default();
+        // A merge can leave the counter behind this sender's own last slot
+        while self.message_index.get(&message_index_key(case_id, sequence, &message.sender_id))?.is_some() {
+            sequence += 1;
         }
+        message.sequence = sequence;
+        message.message_id = message.compute_id(case_id);
 
-        self.access_requests.insert(target_user_id, requests)?;
+        self.message_index.insert(message_index_key(case_id, sequence, &message.sender_id), message.message_id.clone())?;
+        self.message_counts.insert(case_id.to_string(), sequence + 1)?;
+        self.messages.insert(message_key(case_id, &message.message_id), message.clone())?;
+
+        // Only the sender writes their own log, so it never has gaps or ties
+        let count_key = sender_count_key(case_id, &message.sender_id);
+        let entry = self.sender_counts.get(&count_key)?.unwrap_or_default();
+        self.sender_logs.insert(sender_log_key(case_id, &message.sender_id, entry), message.message_id.clone())?;
+        self.sender_counts.insert(count_key, entry + 1)?;
         Ok(())
     }
 
-    pub fn get_user_access_requests(
+    // Expired messages read as missing even before they are purged
+    fn get_message(&self, case_id: &str, message_id: &str) -> Result<Option<EncryptedMessage>, CipherError> {
+        let now = time_now();
+        Ok(self.messages.get(&message_key(case_id, message_id))?
+            .filter(|message| !message.is_expired(now)))
+    }
+
+    // Single membership gate for every messaging entry point: admins, lawyers
+    // and clients alike pass only while they hold a member record on the case.
+    fn require_case_member(&self, case_id: &str, member_id: &str, action: &'static str) -> Result<(LegalCase, CaseMember), CipherError> {
+        let case = self.cases.get(case_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.to_string() })?;
+        let member = case.member(member_id)
+            .cloned()
+            .ok_or(CipherError::Unauthorized { action })?;
+        Ok((case, member))
+    }
+
+    // Message reads need ReadMessages and go through the same consent gate
+    // as case documents
+    fn require_message_reader(&self, case_id: &str, member_id: &str, action: &'static str) -> Result<(LegalCase, CaseMember), CipherError> {
+        let (case, member) = self.require_case_member(case_id, member_id, action)?;
+        case.require(member_id, CasePermission::ReadMessages)?;
+        if let Some(client_id) = case.consent_client_for(member_id) {
+            if !self.has_live_consent(client_id, member_id, "read")? {
+                return Err(CipherError::ConsentRequired { scope: "read" });
+            }
+        }
+        Ok((case, member))
+    }
+
+    // Edits and retractions are logged like sends, so delta sync finds
+    // changes to old messages by when they changed
+    fn record_change(&mut self, case_id: &str, message: &EncryptedMessage) -> Result<(), CipherError> {
+        let mut change = self.change_counts.get(case_id)?.unwrap_or_default();
+        while self.message_changes.get(&message_index_key(case_id, change, &message.sender_id))?.is_some() {
+            change += 1;
+        }
+        self.message_changes.insert(message_index_key(case_id, change, &message.sender_id), message.message_id.clone())?;
+        self.change_counts.insert(case_id.to_string(), change + 1)?;
+        Ok(())
+    }
+
+    // Resolves one sequence slot, ordered by sender; usually a single
+    // message, more when sends raced. Deleted messages are skipped.
+    fn messages_at(&self, case: &LegalCase, sequence: u64) -> Result<Vec<EncryptedMessage>, CipherError> {
+        self.slot_messages(&self.message_index, case, sequence)
+    }
+
+    fn slot_messages(
+        &self,
+        index: &UnorderedMap<String, String>,
+        case: &LegalCase,
+        sequence: u64
+    ) -> Result<Vec<EncryptedMessage>, CipherError> {
+        let mut messages = Vec::new();
+        for sender_id in case.message_senders() {
+            if let Some(message_id) = index.get(&message_index_key(&case.case_id, sequence, sender_id))? {
+                if let Some(message) = self.get_message(&case.case_id, &message_id)? {
+                    messages.push(message);
+                }
+            }
+        }
+        messages.sort_by(|a, b| a.sender_id.cmp(&b.sender_id));
+        Ok(messages)
+    }
+
+    // Walks the index in send order, skipping entries whose message was deleted
+    fn load_case_messages(&self, case: &LegalCase) -> Result<Vec<EncryptedMessage>, CipherError> {
+        let count = self.message_counts.get(&case.case_id)?.unwrap_or_default();
+        let mut messages = Vec::new();
+        for sequence in 0..count {
+            messages.extend(self.messages_at(case, sequence)?);
+        }
+        Ok(messages)
+    }
+
+    // Unread counts walk each sender's log from the member's read mark for
+    // that sender. Slots can be filled out of order after a merge, but a
+    // sender's own log only ever grows at the end, so no message can land
+    // behind a mark. Nothing sent before a member joined is visible to them,
+    // so joining starts every mark at the end of its log.
+    fn start_read_mark(&mut self, case: &LegalCase, member_id: &str) -> Result<(), CipherError> {
+        for sender_id in case.message_senders() {
+            let sent = self.sender_counts.get(&sender_count_key(&case.case_id, sender_id))?.unwrap_or_default();
+            self.read_marks.insert(read_mark_key(&case.case_id, member_id, sender_id), sent)?;
+        }
+        Ok(())
+    }
+
+    // The sender's messages from the member's mark onwards, skipping deleted ones
+    fn unread_candidates(&self, case: &LegalCase, member_id: &str, sender_id: &str) -> Result<Vec<EncryptedMessage>, CipherError> {
+        let mark = self.read_marks.get(&read_mark_key(&case.case_id, member_id, sender_id))?.unwrap_or_default();
+        let sent = self.sender_counts.get(&sender_count_key(&case.case_id, sender_id))?.unwrap_or_default();
+        let mut messages = Vec::new();
+        for entry in mark..sent {
+            let message = match self.sender_logs.get(&sender_log_key(&case.case_id, sender_id, entry))? {
+                Some(message_id) => self.get_message(&case.case_id, &message_id)?,
+                None => None,
+            };
+            messages.extend(message);
+        }
+        Ok(messages)
+    }
+
+    // Moves the mark for one sender past every leading message the member
+    // has nothing left to read in
+    fn advance_read_mark(&mut self, case: &LegalCase, member: &CaseMember, sender_id: &str) -> Result<(), CipherError> {
+        let key = read_mark_key(&case.case_id, &member.member_id, sender_id);
+        let start = self.read_marks.get(&key)?.unwrap_or_default();
+        let sent = self.sender_counts.get(&sender_count_key(&case.case_id, sender_id))?.unwrap_or_default();
+        let mut mark = start;
+        while mark < sent {
+            let unread = match self.sender_logs.get(&sender_log_key(&case.case_id, sender_id, mark))? {
+                Some(message_id) => self.get_message(&case.case_id, &message_id)?
+                    .is_some_and(|message| message.is_unread_by(member)),
+                None => false,
+            };
+            if unread {
+                break;
+            }
+            mark += 1;
+        }
+        if mark != start {
+            self.read_marks.insert(key, mark)?;
+        }
+        Ok(())
+    }
+
+    fn has_live_consent(&self, client_id: &str, lawyer_id: &str, operation: &str) -> Result<bool, CipherError> {
+        let consent_key = format!("{}:{}", client_id, lawyer_id);
+        Ok(self.consents.get(&consent_key)?
+            .map_or(false, |consent| consent.is_live() && consent.covers(operation)))
+    }
+
+    // True when lawyer_id is counsel on any case where client_id is the client
+    fn represents(&self, lawyer_id: &str, client_id: &str) -> Result<bool, CipherError> {
+        Ok(self.cases.entries()?
+            .any(|(_, case)| case.client_id() == Some(client_id) && case.consent_client_for(lawyer_id).is_some()))
+    }
+
+    // Only counsel working on a client's material need that client's live
+    // consent: counsel on the document's case, or for a vault document, the
+    // owner's counsel. Everyone else reads on their grant alone.
+    fn consent_allows(&self, doc: &LegalDocument, caller: &str, operation: &str) -> Result<bool, CipherError> {
+        if doc.owner_id == caller {
+            return Ok(true);
+        }
+
+        let client_id = match &doc.case_id {
+            Some(case_id) => self.cases.get(case_id)?
+                .and_then(|case| case.consent_client_for(caller).map(str::to_string)),
+            None => self.represents(caller, &doc.owner_id)?
+                .then(|| doc.owner_id.clone())
+        };
+        match client_id {
+            Some(client_id) => self.has_live_consent(&client_id, caller, operation),
+            None => Ok(true)
+        }
+    }
+
+    // Drops a member from the case and revokes their grants on its documents
+    fn detach_case_member(&mut self, case: &mut LegalCase, member_id: &str) -> Result<(), CipherError> {
+        if !case.is_member(member_id) {
+            return Err(CipherError::NotFound { kind: "case_member", id: member_id.to_string() });
+        }
+        case.members.retain(|m| m.member_id != member_id);
+        if !case.former_members.iter().any(|id| id == member_id) {
+            case.former_members.push(member_id.to_string());
+        }
+
+        let revoked_by = caller_id();
+        for doc_hash in &case.related_documents {
+            if let Some(mut doc) = self.documents.get(doc_hash)? {
+                if doc.grants.iter().any(|grant| grant.grantee_id == member_id) {
+                    doc.grants.retain(|grant| grant.grantee_id != member_id);
+                    self.sync_chain_access(&doc.root_hash, &doc.grants)?;
+
+                    emit!(CipherEvent::AccessRevoked {
+                        doc_hash,
+                        grantee_id: member_id,
+                        revoked_by: &revoked_by
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Owners and live grantees read through their grants; case documents are
+    // also readable by members holding ViewDocuments. Consent gates both.
+    fn can_read_document(&self, doc: &LegalDocument, caller: &str) -> Result<bool, CipherError> {
+        let listed = doc.has_access(caller, AccessLevel::Read);
+        let via_case = match &doc.case_id {
+            Some(case_id) if !listed => self.cases.get(case_id)?
+                .map_or(false, |case| case.require(caller, CasePermission::ViewDocuments).is_ok()),
+            _ => false,
+        };
+        if !listed && !via_case {
+            return Ok(false);
+        }
+        self.consent_allows(doc, caller, "read")
+    }
+
+    // Only the owner sees who else holds access to a document
+    fn summarize_document(&self, doc: &LegalDocument, viewer: &str) -> Result<DocumentSummary, CipherError> {
+        let analysis = match &doc.ai_analysis_id {
+            Some(analysis_id) => self.ai_results.get(analysis_id)?,
+            None => None,
+        };
+        let analysis_status = match &analysis {
+            None => AnalysisStatus::NotRequested,
+            Some(result) if result.completed_at.is_some() => AnalysisStatus::Completed,
+            Some(_) => AnalysisStatus::Pending,
+        };
+        let grants = if doc.owner_id == viewer {
+            doc.live_grants().cloned().collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(DocumentSummary {
+            document_hash: doc.document_hash.clone(),
+            document_type: doc.document_type.clone(),
+            owner_id: doc.owner_id.clone(),
+            case_id: doc.case_id.clone(),
+            size: doc.size,
+            chunk_count: doc.chunk_hashes.len() as u32,
+            version: doc.version,
+            timestamp: doc.timestamp,
+            grants,
+            analysis_id: analysis.map(|result| result.analysis_id),
+            analysis_status,
+        })
+    }
+
+    // Case uploads need UploadDocument on an open case and, for counsel,
+    // the client's write consent.
+    fn check_case_upload(&self, author_id: &str, case_id: &str) -> Result<(), CipherError> {
+        let case = self.cases.get(case_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.to_string() })?;
+        case.require(author_id, CasePermission::UploadDocument)?;
+        if !case.status.accepts_activity() {
+            return Err(CipherError::Conflict { reason: "case is closed" });
+        }
+        if let Some(client_id) = case.consent_client_for(author_id) {
+            if !self.has_live_consent(client_id, author_id, "write")? {
+                return Err(CipherError::ConsentRequired { scope: "write" });
+            }
+        }
+        Ok(())
+    }
+
+    // Validates where a new document will land before any content is stored:
+    // the vault, a case, or the next version of parent_hash.
+    fn check_document_target(
         &self,
-caller_id: String,
-    ) -> Result<Vec<String>, Error> {
+        author_id: &str,
+        doc_hash: &str,
+        case_id: Option<&str>,
+        parent_hash: Option<&str>
+    ) -> Result<(), CipherError> {
+        if self.documents.get(doc_hash)?.is_some() {
+            return Err(CipherError::Conflict { reason: "document already exists" });
+        }
+
+        let parent_hash = match (parent_hash, case_id) {
+            (Some(_), Some(_)) => return Err(CipherError::InvalidInput { field: "case_id" }),
+            (Some(parent_hash), None) => parent_hash,
+            (None, Some(case_id)) => return self.check_case_upload(author_id, case_id),
+            (None, None) => return Ok(()),
+        };
+
+        let parent = self.documents.get(parent_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "document", id: parent_hash.to_string() })?;
+        if parent.superseded_by.is_some() {
+            return Err(CipherError::Conflict { reason: "parent is not the latest version" });
+        }
+        if parent.owner_id != author_id
+            && !(parent.has_access(author_id, AccessLevel::Write) && self.consent_allows(&parent, author_id, "write")?)
+        {
+            return Err(CipherError::Unauthorized { action: "upload_document_version" });
+        }
+        match &parent.case_id {
+            Some(case_id) => self.check_case_upload(author_id, case_id),
+            None => Ok(()),
+        }
+    }
+
+    // Identical chunks are stored once, whichever document they belong to
+    fn store_chunk(&mut self, data: &[u8]) -> Result<String, CipherError> {
+        let chunk_hash = hex::encode(Sha256::digest(data));
+        if self.chunks.get(&chunk_hash)?.is_none() {
+            self.chunks.insert(chunk_hash.clone(), data.to_vec())?;
+        }
+        Ok(chunk_hash)
+    }
+
+    // Chunks are shared, so one is only deleted once no document and no
+    // upload in progress refers to it any more
+    fn release_chunks(&mut self, mut candidates: Vec<String>) -> Result<(), CipherError> {
+        if candidates.is_empty() {
+            return Ok(());
+        }
+        let mut referenced = HashSet::new();
+        for (_, doc) in self.documents.entries()? {
+            referenced.extend(doc.chunk_hashes);
+        }
+        for (_, session) in self.uploads.entries()? {
+            referenced.extend(session.chunk_hashes.into_iter().flatten());
+        }
+
+        candidates.sort();
+        candidates.dedup();
+        for chunk_hash in candidates {
+            if !referenced.contains(&chunk_hash) {
+                self.chunks.remove(&chunk_hash)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn store_chunks(&mut self, content: &[u8]) -> Result<Vec<String>, CipherError> {
+        content.chunks(MAX_CHUNK_SIZE)
+            .map(|chunk| self.store_chunk(chunk))
+            .collect()
+    }
+
+    // Creates version 1 of a document whose chunks are already stored.
+    // Callers run check_document_target first.
+    fn insert_document(
+        &mut self,
+        owner_id: &str,
+        doc_hash: String,
+        document_type: String,
+        case_id: Option<String>,
+        chunk_hashes: Vec<String>,
+        size: u64
+    ) -> Result<(), CipherError> {
+        if let Some(case_id) = &case_id {
+            let mut case = self.cases.get(case_id)?
+                .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+            if !case.related_documents.contains(&doc_hash) {
+                case.related_documents.push(doc_hash.clone());
+                self.cases.insert(case_id.clone(), case)?;
+            }
+        }
+
+        let document = LegalDocument {
+            document_hash: doc_hash.clone(),
+            document_type: document_type.clone(),  // e.g., "image/png"
+            size,
+            chunk_hashes,
+            owner_id: owner_id.to_string(),
+            case_id,
+            grants: Vec::new(),
+            ai_analysis_id: None,
+            timestamp: time_now(),
+            version: 1,
+            root_hash: doc_hash.clone(),
+            parent_hash: None,
+            author_id: owner_id.to_string(),
+            change_note: None,
+            superseded_by: None,
+        };
+        self.register_document(document)?;
+
+        emit!(CipherEvent::DocumentUploaded {
+            doc_hash: &doc_hash,
+            doc_type: &document_type
+        });
+        Ok(())
+    }
+
+    // Appends a version on top of parent_hash. The new version keeps the
+    // owner, case and grants of its parent. Callers run
+    // check_document_target first.
+    fn insert_document_version(
+        &mut self,
+        author_id: &str,
+        parent_hash: String,
+        doc_hash: String,
+        change_note: Option<String>,
+        chunk_hashes: Vec<String>,
+        size: u64
+    ) -> Result<u32, CipherError> {
+        let mut parent = self.documents.get(&parent_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "document", id: parent_hash.clone() })?;
+
+        if let Some(case_id) = parent.case_id.clone() {
+            let mut case = self.cases.get(&case_id)?
+                .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+            // The case lists the latest version of each document
+            match case.related_documents.iter_mut().find(|hash| **hash == parent_hash) {
+                Some(slot) => *slot = doc_hash.clone(),
+                None => case.related_documents.push(doc_hash.clone()),
+            }
+            self.cases.insert(case_id, case)?;
+        }
+
+        let version = parent.version + 1;
+        let document = LegalDocument {
+            document_hash: doc_hash.clone(),
+            document_type: parent.document_type.clone(),
+            size,
+            chunk_hashes,
+            owner_id: parent.owner_id.clone(),
+            case_id: parent.case_id.clone(),
+            grants: parent.grants.clone(),
+            ai_analysis_id: None,
+            timestamp: time_now(),
+            version,
+            root_hash: parent.root_hash.clone(),
+            parent_hash: Some(parent_hash.clone()),
+            author_id: author_id.to_string(),
+            change_note,
+            superseded_by: None,
+        };
+
+        parent.superseded_by = Some(doc_hash.clone());
+        let root_hash = parent.root_hash.clone();
+        self.documents.insert(parent_hash.clone(), parent)?;
+        self.register_document(document)?;
+
+        emit!(CipherEvent::DocumentVersioned {
+            doc_hash: &doc_hash,
+            root_hash: &root_hash,
+            parent_hash: &parent_hash,
+            version,
+            author: author_id
+        });
+        Ok(version)
+    }
+
+    // Stores a document and appends it to its version chain
+    fn register_document(&mut self, document: LegalDocument) -> Result<(), CipherError> {
+        let mut chain = self.document_versions.get(&document.root_hash)?.unwrap_or_default();
+        chain.push(document.document_hash.clone());
+        self.document_versions.insert(document.root_hash.clone(), chain)?;
+        self.documents.insert(document.document_hash.clone(), document)?;
+        Ok(())
+    }
+
+    // Access is shared by every version of a document, so a grant or
+    // revocation on one version is written through the whole chain.
+    fn sync_chain_access(&mut self, root_hash: &str, grants: &[AccessGrant]) -> Result<(), CipherError> {
+        let chain = self.document_versions.get(root_hash)?.unwrap_or_default();
+        for doc_hash in chain {
+            if let Some(mut doc) = self.documents.get(&doc_hash)? {
+                if doc.grants != grants {
+                    doc.grants = grants.to_vec();
+                    self.documents.insert(doc_hash, doc)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[app::logic]
+impl CipherState {
+    #[app::init]
+    pub fn init() -> Self {
+        Self {
+            messages: UnorderedMap::new(),
+            message_index: UnorderedMap::new(),
+            message_counts: UnorderedMap::new(),
+            message_changes: UnorderedMap::new(),
+            change_counts: UnorderedMap::new(),
+            documents: UnorderedMap::new(),
+            document_versions: UnorderedMap::new(),
+            chunks: UnorderedMap::new(),
+            uploads: UnorderedMap::new(),
+            cases: UnorderedMap::new(),
+            consents: UnorderedMap::new(),
+            ai_results: UnorderedMap::new(),
+            payments: UnorderedMap::new(),
+            user_details: UnorderedMap::new(),
+            access_requests: UnorderedMap::new(),
+            invitations: UnorderedMap::new(),
+            matter_sequences: UnorderedMap::new(),
+            sender_logs: UnorderedMap::new(),
+            sender_counts: UnorderedMap::new(),
+            read_marks: UnorderedMap::new()
+        }
+    }   
+    //User Details Access Control 
+    pub fn update_user_details(
+        &mut self,
+        details: user_details
+    ) -> Result<(), CipherError> {
+        let caller = caller_id();
+
+        // Prevent updating someone else’s details.
+        if caller != details.user_id {
+            return Err(CipherError::Unauthorized { action: "update_user_details" });
+        }
+        
     
-        let requests = self.access_requests.get(&caller_id)?.unwrap_or_default();
+        let mut current = self.user_details.get(&caller)?.unwrap_or(user_details{
+            user_id: caller.clone(),
+            name: None,
+            phone: None,
+            email: None,
+            wallet_id: None,
+            access_list: Vec::new(),
+        });
+
+
+        if details.name.is_some() {
+            current.name = details.name;
+        }
+        if details.phone.is_some() {
+            current.phone = details.phone;
+        }
+        if details.email.is_some() {
+            current.email = details.email;
+        }
+        if !details.access_list.is_empty() {
+            current.access_list = details.access_list;
+        }
+
+        self.user_details.insert(caller, current)?;
+        Ok(())
+    }
+    pub fn request_user_details_access(
+        &mut self,
+        target_user_id: String,
+    ) -> Result<(), CipherError> {
+        let requester_id = caller_id();
+
+        if target_user_id == requester_id {
+            return Err(CipherError::InvalidInput { field: "target_user_id" });
+        }
+
+
+        let mut requests = self.access_requests.get(&target_user_id)?.unwrap_or_default();
+
+        if !requests.contains(&requester_id) {
+            requests.push(requester_id);
+        }
+
+        self.access_requests.insert(target_user_id, requests)?;
+        Ok(())
+    }
+
+    pub fn get_user_access_requests(
+        &self,
+    ) -> Result<Vec<String>, CipherError> {
+        let caller = caller_id();
+        let requests = self.access_requests.get(&caller)?.unwrap_or_default();
         Ok(requests)
     }
     
 
     pub fn grant_user_details_access(
         &mut self,
-        grantee_id: String,
-        caller_id: String
-    ) -> Result<(), Error> {
-        let caller = caller_id.clone();
+        grantee_id: String
+    ) -> Result<(), CipherError> {
+        let caller = caller_id();
 
         let mut details = self.user_details.get(&caller)?
-            .ok_or(Error::msg("User details not found"))?;
+            .ok_or_else(|| CipherError::NotFound { kind: "user_details", id: caller.clone() })?;
 
         // Add the grantee if not already permitted.
         if !details.access_list.contains(&grantee_id) {
@@ -269,29 +1406,28 @@ caller_id: String,
 
     pub fn get_user_details(
         &self,
-        user_id: String,
-        caller_id: String
-    ) -> Result<user_details, Error> {
-        let caller = caller_id;
+        user_id: String
+    ) -> Result<user_details, CipherError> {
+        let caller = caller_id();
         let details = self.user_details.get(&user_id)?
-            .ok_or(Error::msg("User details not found"))?;
+            .ok_or_else(|| CipherError::NotFound { kind: "user_details", id: user_id.clone() })?;
 
         // Allow access if the caller is the owner or in the access list.
         if details.user_id == caller || details.access_list.contains(&caller) {
             Ok(details)
         } else {
-            Err(Error::msg("Access denied"))
+            Err(CipherError::Unauthorized { action: "get_user_details" })
         }
     }
     pub fn get_accessible_user_details(
         &self,
-        caller_id: String,
-    ) -> Result<Vec<user_details>, Error> {
+    ) -> Result<Vec<user_details>, CipherError> {
+        let caller = caller_id();
         let mut accessible_details = Vec::new();
         for entry in self.user_details.entries()? {
             let details = entry.1;
             // Include only if the user isn't the caller and caller is in the access_list.
-            if details.user_id != caller_id && details.access_list.contains(&caller_id) {
+            if details.user_id != caller && details.access_list.contains(&caller) {
                 accessible_details.push(details);
             }
         }
@@ -300,60 +1436,126 @@ caller_id: String,
     // Secure Messaging
 pub fn send_message(
     &mut self,
-    sender_id: String,
     case_id: String,
     ciphertext: Vec<u8>,
     iv: Vec<u8>,
-    mode: MessageMode 
-) -> Result<(), Error> {
+    mode: MessageMode,
+    reply_to: Option<String>,
+    recipient_ids: Option<Vec<String>>
+) -> Result<String, CipherError> {
+    let sender_id = caller_id();
     let sender_str = sender_id.clone();
-    let case = self.cases.get(&case_id)?
-        .ok_or(Error::msg("Case not found"))?;
-    if sender_str != case.client_id && !case.lawyer_ids.contains(&sender_str) {
-        return Err(Error::msg("Unauthorized sender"));
+    let (case, _) = self.require_case_member(&case_id, &sender_str, "send_message")?;
+    case.require(&sender_str, CasePermission::SendMessage)?;
+    if !case.status.accepts_activity() {
+        return Err(CipherError::Conflict { reason: "case is closed" });
     }
 
-    let message = EncryptedMessage {
+    let timestamp = time_now();
+    let expires_at = match mode {
+        MessageMode::VanishAfter { seconds: 0 } => {
+            return Err(CipherError::InvalidInput { field: "mode" });
+        },
+        MessageMode::VanishAfter { seconds } => Some(timestamp.saturating_add(seconds.saturating_mul(1_000_000_000))),
+        _ => None
+    };
+
+    // Replies join the thread of their parent; a top-level parent is the root
+    let parent = match &reply_to {
+        Some(parent_id) => Some(
+            self.get_message(&case_id, parent_id)?
+                .filter(|parent| parent.is_addressed_to(&sender_str))
+                .ok_or_else(|| CipherError::NotFound { kind: "message", id: parent_id.clone() })?
+        ),
+        None => None
+    };
+    let thread_root = parent.as_ref()
+        .map(|parent| parent.thread_root.clone().unwrap_or_else(|| parent.message_id.clone()));
+
+    // A subset of members makes this a direct or sub-group message; the
+    // sender is always part of the audience.
+    let recipient_ids = match (recipient_ids, &parent) {
+        (Some(mut recipients), _) => {
+            if recipients.iter().any(|id| case.require(id, CasePermission::ReadMessages).is_err()) {
+                return Err(CipherError::InvalidInput { field: "recipient_ids" });
+            }
+            recipients.push(sender_str.clone());
+            recipients.sort();
+            recipients.dedup();
+            if recipients.len() < 2 {
+                return Err(CipherError::InvalidInput { field: "recipient_ids" });
+            }
+            recipients
+        },
+        // A reply stays within the audience of the message it answers
+        (None, Some(parent)) => {
+            let mut audience = parent.audience();
+            audience.push(parent.sender_id.clone());
+            audience.push(sender_str.clone());
+            audience.retain(|id| case.require(id, CasePermission::ReadMessages).is_ok());
+            audience.sort();
+            audience.dedup();
+            audience
+        },
+        (None, None) => case.message_readers()
+    };
+
+    let mut message = EncryptedMessage {
+        message_id: String::new(),
+        sequence: 0,
         ciphertext,
         iv,
         sender_id: sender_str,
-        recipient_ids: case.get_participants(), 
-        timestamp: env::time_now(),
+        recipient_ids,
+        timestamp,
         mode,
-        read_receipts: Vec::new()
+        expires_at,
+        reply_to,
+        thread_root,
+        read_receipts: Vec::new(),
+        revisions: Vec::new(),
+        edited_at: None,
+        retracted_at: None,
+        updated_at: timestamp
     };
+    self.append_message(&case_id, &mut message)?;
 
-    let mut case_messages = self.messages.get(&case_id)?.unwrap_or_default();
-    case_messages.push(message.clone());
-    self.messages.insert(case_id.clone(), case_messages)?;
-
-    app::emit!(CipherEvent::MessageSent {
+    // Events reach every context member, so they carry no content;
+    // recipients fetch the message through the read paths
+    emit!(CipherEvent::MessageSent {
         case_id: &case_id,
         sender: &sender_id,
-        message: &message
+        message_id: &message.message_id,
+        sequence: message.sequence,
+        reply_to: message.reply_to.as_deref(),
+        thread_root: message.thread_root.as_deref()
     });
     
-    Ok(())
+    Ok(message.message_id)
 }
 
 // Enhanced read marking with mode handling
 pub fn mark_message_read(
     &mut self,
     case_id: String,
-    message_index: usize,
-    reader_id: String
-) -> Result<(), Error> {
-    let mut messages = self.messages.get(&case_id)?.unwrap_or_default();
+    message_id: String
+) -> Result<(), CipherError> {
+    let reader_id = caller_id();
+    let (case, member) = self.require_case_member(&case_id, &reader_id, "mark_message_read")?;
+    let key = message_key(&case_id, &message_id);
     
-    if let Some(message) = messages.get_mut(message_index) {
+    if let Some(mut message) = self.get_message(&case_id, &message_id)? {
        
         if !message.recipient_ids.contains(&reader_id) {
-            return Err(Error::msg("Unauthorized read attempt"));
+            return Err(CipherError::Unauthorized { action: "mark_message_read" });
         }
 
        
-        if !message.read_receipts.contains(&reader_id) {
-            message.read_receipts.push(reader_id.clone());
+        if message.read_at(&reader_id).is_none() {
+            message.read_receipts.push(ReadReceipt {
+                reader_id: reader_id.clone(),
+                read_at: time_now()
+            });
         }
 
         
@@ -364,160 +1566,639 @@ pub fn mark_message_read(
                 
                
                 if message.recipient_ids.is_empty() {
-                    messages.remove(message_index);
+                    self.messages.remove(&key)?;
+                    return self.advance_read_mark(&case, &member, &message.sender_id);
                 }
             },
-            MessageMode::Persistent => {
+            MessageMode::Persistent | MessageMode::VanishAfter { .. } => {
                
             }
         }
         
-        self.messages.insert(case_id, messages)?;
-        Ok(())
+        let sender_id = message.sender_id.clone();
+        self.messages.insert(key, message)?;
+        self.advance_read_mark(&case, &member, &sender_id)
     } else {
-        Err(Error::msg("Message not found"))
+        Err(CipherError::NotFound { kind: "message", id: message_id })
     }
 }
 
-// Updated message retrieval with mode consideration
-pub fn get_visible_messages(
-    &self,
+pub fn edit_message(
+    &mut self,
     case_id: String,
-    requester_id: String
-) -> Result<Vec<EncryptedMessage>, Error> {
-    let case = self.cases.get(&case_id)?
-        .ok_or(Error::msg("Case not found"))?;
-
-        if requester_id != case.client_id 
-        && !case.lawyer_ids.contains(&requester_id)
-        && requester_id != case.admin_id 
-    {
-        return Err(Error::msg("Unauthorized access to case chat"));
+    message_id: String,
+    ciphertext: Vec<u8>,
+    iv: Vec<u8>
+) -> Result<(), CipherError> {
+    let sender_id = caller_id();
+    let (case, _) = self.require_case_member(&case_id, &sender_id, "edit_message")?;
+    if !case.status.accepts_activity() {
+        return Err(CipherError::Conflict { reason: "case is closed" });
     }
-    Ok(self.messages.get(&case_id)?.unwrap_or_default())
-}
 
-    pub fn get_case_messages(
-        &self,
-        case_id: String,
-        requester_id: String
-    ) -> Result<Vec<EncryptedMessage>, Error> {
-        // Verify case exists
-        let case = self.cases.get(&case_id)?
-            .ok_or(Error::msg("Case not found"))?;
-    
-        // Validate requester's access rights
-        if requester_id != case.client_id 
-        && !case.lawyer_ids.contains(&requester_id)
-        && requester_id != case.admin_id 
-    {
-        return Err(Error::msg("Unauthorized access to case chat"));
+    let mut message = self.get_message(&case_id, &message_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "message", id: message_id.clone() })?;
+    if message.sender_id != sender_id {
+        return Err(CipherError::Unauthorized { action: "edit_message" });
     }
-        // Retrieve all messages for the case
-        Ok(self.messages.get(&case_id)?.unwrap_or_default())
+    if message.retracted_at.is_some() {
+        return Err(CipherError::Conflict { reason: "message was retracted" });
     }
 
-    // Callback from ICP AI Canister
-    pub fn update_ai_analysis(
-        &mut self,
-        analysis_id: String,
-        summary: String,
-        risks_detected: u8,
-        recommendations: Vec<String>
-    ) -> Result<(), Error> {
-        let mut analysis = self.ai_results.get(&analysis_id)?
-            .ok_or(Error::msg("Analysis not found"))?;
+    let now = time_now();
+    message.push_revision(now);
+    message.ciphertext = ciphertext;
+    message.iv = iv;
+    message.edited_at = Some(now);
+    message.updated_at = now;
+    self.record_change(&case_id, &message)?;
+    self.messages.insert(message_key(&case_id, &message_id), message)?;
 
-        let doc_hash = analysis.document_hash.clone();
-        analysis.summary = summary;
-        analysis.risks_detected = risks_detected;
-        analysis.recommendations = recommendations;
+    emit!(CipherEvent::MessageEdited {
+        case_id: &case_id,
+        message_id: &message_id,
+        sender: &sender_id
+    });
+
+    Ok(())
+}
+
+// Leaves a tombstone in place of the content so every member sees the retraction
+pub fn retract_message(
+    &mut self,
+    case_id: String,
+    message_id: String
+) -> Result<(), CipherError> {
+    let sender_id = caller_id();
+    self.require_case_member(&case_id, &sender_id, "retract_message")?;
+    let mut message = self.get_message(&case_id, &message_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "message", id: message_id.clone() })?;
+    if message.sender_id != sender_id {
+        return Err(CipherError::Unauthorized { action: "retract_message" });
+    }
+    if message.retracted_at.is_some() {
+        return Err(CipherError::Conflict { reason: "message was retracted" });
+    }
+
+    let now = time_now();
+    message.push_revision(now);
+    message.ciphertext = Vec::new();
+    message.iv = Vec::new();
+    message.retracted_at = Some(now);
+    message.updated_at = now;
+    self.record_change(&case_id, &message)?;
+    self.messages.insert(message_key(&case_id, &message_id), message)?;
+
+    emit!(CipherEvent::MessageRetracted {
+        case_id: &case_id,
+        message_id: &message_id,
+        sender: &sender_id
+    });
+
+    Ok(())
+}
+
+// Updated message retrieval with mode consideration
+pub fn get_visible_messages(
+    &self,
+    case_id: String
+) -> Result<Vec<EncryptedMessage>, CipherError> {
+    let requester_id = caller_id();
+    let (case, member) = self.require_message_reader(&case_id, &requester_id, "read_messages")?;
+
+    let messages = self.load_case_messages(&case)?
+        .into_iter()
+        .filter(|message| message.visible_to(&member))
+        .collect();
+    Ok(messages)
+}
+
+    pub fn get_case_messages(
+        &self,
+        case_id: String
+    ) -> Result<Vec<EncryptedMessage>, CipherError> {
+        let requester_id = caller_id();
+        let (case, _) = self.require_message_reader(&case_id, &requester_id, "read_messages")?;
+        // Retrieve the messages addressed to the requester
+        let messages = self.load_case_messages(&case)?
+            .into_iter()
+            .filter(|message| message.is_addressed_to(&requester_id))
+            .collect();
+        Ok(messages)
+    }
+
+    // Cursor pagination over sequence numbers: with no cursor returns the
+    // latest page, `before` walks back into history and `after` walks
+    // forward. Cursors stay valid after the message they came from expires
+    // or is deleted. Messages sharing a sequence never straddle two pages.
+    pub fn get_case_messages_page(
+        &self,
+        case_id: String,
+        before: Option<u64>,
+        after: Option<u64>,
+        limit: u32
+    ) -> Result<MessagePage, CipherError> {
+        let requester_id = caller_id();
+        let (case, _) = self.require_message_reader(&case_id, &requester_id, "read_messages")?;
+        if before.is_some() && after.is_some() {
+            return Err(CipherError::InvalidInput { field: "before" });
+        }
+
+        let limit = (limit as usize).clamp(1, MAX_MESSAGE_PAGE);
+        let count = self.message_counts.get(&case_id)?.unwrap_or_default();
+        let mut messages = Vec::new();
+        let mut has_more = false;
+
+        let cursor = if let Some(after) = after {
+            let mut cursor = after;
+            let mut sequence = after.saturating_add(1);
+            while sequence < count {
+                let slot: Vec<_> = self.messages_at(&case, sequence)?
+                    .into_iter()
+                    .filter(|message| message.is_addressed_to(&requester_id))
+                    .collect();
+                if !messages.is_empty() && messages.len() + slot.len() > limit {
+                    has_more = true;
+                    break;
+                }
+                messages.extend(slot);
+                cursor = sequence;
+                sequence += 1;
+            }
+            cursor
+        } else {
+            let mut sequence = before.unwrap_or(count).min(count);
+            while sequence > 0 {
+                let slot: Vec<_> = self.messages_at(&case, sequence - 1)?
+                    .into_iter()
+                    .filter(|message| message.is_addressed_to(&requester_id))
+                    .collect();
+                if !messages.is_empty() && messages.len() + slot.len() > limit {
+                    has_more = true;
+                    break;
+                }
+                messages.extend(slot.into_iter().rev());
+                sequence -= 1;
+            }
+            messages.reverse();
+            sequence
+        };
+
+        Ok(MessagePage { messages, has_more, cursor })
+    }
+
+    // Delta sync after reconnecting: messages sent, edited or retracted
+    // after `since`, in send order
+    pub fn get_messages_since(
+        &self,
+        case_id: String,
+        since: u64
+    ) -> Result<Vec<EncryptedMessage>, CipherError> {
+        let requester_id = caller_id();
+        let (case, _) = self.require_message_reader(&case_id, &requester_id, "read_messages")?;
+
+        // Slot order is not time order: concurrent sends share a slot and a
+        // node with a stale counter appends below newer messages, so both
+        // logs are walked in full rather than stopping at the first old slot.
+        let mut changed: Vec<EncryptedMessage> = Vec::new();
+        let count = self.message_counts.get(&case_id)?.unwrap_or_default();
+        for sequence in 0..count {
+            changed.extend(self.messages_at(&case, sequence)?
+                .into_iter()
+                .filter(|message| message.timestamp > since));
+        }
+        let changes = self.change_counts.get(&case_id)?.unwrap_or_default();
+        for change in 0..changes {
+            for message in self.slot_messages(&self.message_changes, &case, change)? {
+                if message.updated_at > since && !changed.iter().any(|seen| seen.message_id == message.message_id) {
+                    changed.push(message);
+                }
+            }
+        }
+
+        changed.retain(|message| message.is_addressed_to(&requester_id));
+        changed.sort_by(|a, b| (a.sequence, &a.sender_id).cmp(&(b.sequence, &b.sender_id)));
+        Ok(changed)
+    }
+
+    // Root message followed by its replies, in send order
+    pub fn get_thread(
+        &self,
+        case_id: String,
+        thread_root: String
+    ) -> Result<Vec<EncryptedMessage>, CipherError> {
+        let requester_id = caller_id();
+        let (case, _) = self.require_message_reader(&case_id, &requester_id, "read_messages")?;
+
+        let messages = self.load_case_messages(&case)?
+            .into_iter()
+            .filter(|message| {
+                message.is_addressed_to(&requester_id)
+                    && (message.message_id == thread_root
+                        || message.thread_root.as_deref() == Some(thread_root.as_str()))
+            })
+            .collect();
+        Ok(messages)
+    }
+
+    // Per-recipient status. Messages live in replicated context state, so a
+    // message counts as delivered to every recipient when it is stored.
+    pub fn get_message_status(
+        &self,
+        case_id: String,
+        message_id: String
+    ) -> Result<MessageStatus, CipherError> {
+        let requester_id = caller_id();
+        self.require_message_reader(&case_id, &requester_id, "get_message_status")?;
+        let message = self.get_message(&case_id, &message_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "message", id: message_id.clone() })?;
+        if !message.is_addressed_to(&requester_id) && message.read_at(&requester_id).is_none() {
+            return Err(CipherError::Unauthorized { action: "get_message_status" });
+        }
+
+        let recipients = message.audience().into_iter()
+            .filter(|member_id| *member_id != message.sender_id)
+            .map(|member_id| RecipientStatus {
+                read_at: message.read_at(&member_id),
+                delivered_at: message.timestamp,
+                member_id
+            })
+            .collect();
+
+        Ok(MessageStatus { message_id, recipients })
+    }
+
+    // Unread messages per case for the caller's dashboard badges
+    pub fn get_unread_counts(&self) -> Result<HashMap<String, u64>, CipherError> {
+        let user_id = caller_id();
+        let mut counts = HashMap::new();
+
+        for (case_id, case) in self.cases.entries()? {
+            let Ok(member) = case.require(&user_id, CasePermission::ReadMessages) else {
+                continue;
+            };
+            let mut unread = 0;
+            for sender_id in case.message_senders() {
+                unread += self.unread_candidates(&case, &user_id, sender_id)?
+                    .iter().filter(|message| message.is_unread_by(member)).count() as u64;
+            }
+            counts.insert(case_id, unread);
+        }
+
+        Ok(counts)
+    }
+
+    // Maintenance: any member may drop expired messages from storage
+    pub fn purge_expired_messages(&mut self, case_id: String) -> Result<u64, CipherError> {
+        let requester_id = caller_id();
+        let (case, _) = self.require_case_member(&case_id, &requester_id, "purge_expired_messages")?;
+
+        let now = time_now();
+        let count = self.message_counts.get(&case_id)?.unwrap_or_default();
+        let mut purged = 0;
+        for sequence in 0..count {
+            for sender_id in case.message_senders() {
+                let Some(message_id) = self.message_index.get(&message_index_key(&case_id, sequence, sender_id))? else {
+                    continue;
+                };
+                let key = message_key(&case_id, &message_id);
+                if self.messages.get(&key)?.map_or(false, |message| message.is_expired(now)) {
+                    self.messages.remove(&key)?;
+                    purged += 1;
+                }
+            }
+        }
+
+        if purged > 0 {
+            emit!(CipherEvent::MessagesPurged {
+                case_id: &case_id,
+                count: purged
+            });
+        }
+
+        Ok(purged)
+    }
+
+    // Hands a document to an AI canister. Only the identity named here may
+    // post the result, so the requester decides whose analysis is trusted.
+    pub fn request_ai_analysis(
+        &mut self,
+        doc_hash: String,
+        ai_executor_id: String
+    ) -> Result<String, CipherError> {
+        let requester_id = caller_id();
+        let mut doc = self.documents.get(&doc_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "document", id: doc_hash.clone() })?;
+        if doc.owner_id != requester_id
+            && !(doc.has_access(&requester_id, AccessLevel::Write) && self.consent_allows(&doc, &requester_id, "write")?)
+        {
+            return Err(CipherError::Unauthorized { action: "request_ai_analysis" });
+        }
+        if ai_executor_id.is_empty() {
+            return Err(CipherError::InvalidInput { field: "ai_executor_id" });
+        }
+
+        let now = time_now();
+        let mut hasher = Sha256::new();
+        hasher.update(doc_hash.as_bytes());
+        hasher.update(ai_executor_id.as_bytes());
+        hasher.update(now.to_le_bytes());
+        let analysis_id = hex::encode(&hasher.finalize()[..16]);
+
+        self.ai_results.insert(analysis_id.clone(), AIAnalysisResult {
+            analysis_id: analysis_id.clone(),
+            document_hash: doc_hash.clone(),
+            generated_by: ai_executor_id.clone(),
+            timestamp: now,
+            ..Default::default()
+        })?;
+        doc.ai_analysis_id = Some(analysis_id.clone());
+        self.documents.insert(doc_hash.clone(), doc)?;
+
+        emit!(CipherEvent::AnalysisRequested {
+            doc_hash: &doc_hash,
+            ai_canister: &ai_executor_id
+        });
+
+        Ok(analysis_id)
+    }
+
+    // Callback from ICP AI Canister; only the executor named in the request
+    pub fn update_ai_analysis(
+        &mut self,
+        analysis_id: String,
+        summary: String,
+        risks_detected: u8,
+        recommendations: Vec<String>
+    ) -> Result<(), CipherError> {
+        let mut analysis = self.ai_results.get(&analysis_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "analysis", id: analysis_id.clone() })?;
+        if analysis.generated_by != caller_id() {
+            return Err(CipherError::Unauthorized { action: "update_ai_analysis" });
+        }
+
+        let doc_hash = analysis.document_hash.clone();
+        analysis.summary = summary;
+        analysis.risks_detected = risks_detected;
+        analysis.recommendations = recommendations;
+        analysis.timestamp = time_now();
+        analysis.completed_at = Some(analysis.timestamp);
 
         self.ai_results.insert(analysis_id.clone(), analysis)?;
 
-        app::emit!(CipherEvent::AnalysisCompleted {
+        emit!(CipherEvent::AnalysisCompleted {
             doc_hash: &doc_hash,
             analysis_id: &analysis_id
         });
 
         Ok(())
     }
-  
+
+    // Billing: recording a payment against a case needs ManageBilling
+    pub fn initiate_payment(&mut self, case_id: String, amount: u64) -> Result<String, CipherError> {
+        let payer_id = caller_id();
+        let case = self.cases.get(&case_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+        case.require(&payer_id, CasePermission::ManageBilling)?;
+        if !case.status.accepts_activity() {
+            return Err(CipherError::Conflict { reason: "case is closed" });
+        }
+        if amount == 0 {
+            return Err(CipherError::InvalidInput { field: "amount" });
+        }
+
+        let now = time_now();
+        let mut hasher = Sha256::new();
+        hasher.update(case_id.as_bytes());
+        hasher.update(payer_id.as_bytes());
+        hasher.update(amount.to_le_bytes());
+        hasher.update(now.to_le_bytes());
+        let payment_id = hex::encode(&hasher.finalize()[..16]);
+
+        self.payments.insert(payment_id.clone(), PaymentStatus {
+            payment_id: payment_id.clone(),
+            case_id: case_id.clone(),
+            initiated_by: payer_id,
+            amount,
+            status: "pending".to_string(),
+            timestamp: now
+        })?;
+
+        emit!(CipherEvent::PaymentInitiated {
+            case_id: &case_id,
+            payment_id: &payment_id,
+            amount
+        });
+
+        Ok(payment_id)
+    }
+
+    pub fn complete_payment(&mut self, payment_id: String) -> Result<(), CipherError> {
+        let caller = caller_id();
+        let mut payment = self.payments.get(&payment_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "payment", id: payment_id.clone() })?;
+        let case = self.cases.get(&payment.case_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "case", id: payment.case_id.clone() })?;
+        case.require(&caller, CasePermission::ManageBilling)?;
+        if payment.status != "pending" {
+            return Err(CipherError::Conflict { reason: "payment already completed" });
+        }
+
+        payment.status = "completed".to_string();
+        payment.timestamp = time_now();
+        let case_id = payment.case_id.clone();
+        self.payments.insert(payment_id.clone(), payment)?;
+
+        emit!(CipherEvent::PaymentCompleted {
+            case_id: &case_id,
+            payment_id: &payment_id
+        });
+
+        Ok(())
+    }
+
+    pub fn list_case_payments(&self, case_id: String) -> Result<Vec<PaymentStatus>, CipherError> {
+        let caller = caller_id();
+        let case = self.cases.get(&case_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+        case.require(&caller, CasePermission::ManageBilling)?;
+
+        let payments = self.payments.entries()?
+            .filter(|(_, payment)| payment.case_id == case_id)
+            .map(|(_, payment)| payment)
+            .collect();
+        Ok(payments)
+    }
+
     // Case Management
     pub fn open_case(
         &mut self,
         params: CaseCreateParams
-    ) -> Result<(), Error> {
-        let lawyer_id = params.admin_id.clone(); // Admin is the creator
-        if params.case_name.is_empty() || params.description.is_empty() || params.admin_id.is_empty() {
-            return Err(Error::msg("Case name and description are required"));
+    ) -> Result<String, CipherError> {
+        let lawyer_id = caller_id(); // Admin is the creator
+        if params.case_name.is_empty() {
+            return Err(CipherError::InvalidInput { field: "case_name" });
+        }
+        if params.description.is_empty() {
+            return Err(CipherError::InvalidInput { field: "description" });
+        }
+
+        // Matters are numbered per creator rather than per firm: a context
+        // has no notion of a firm, and a shared counter would let nodes
+        // opening cases concurrently draw the same number. Only the creator
+        // writes their counter.
+        let sequence = self.matter_sequences.get(&lawyer_id)?.unwrap_or_default() + 1;
+        self.matter_sequences.insert(lawyer_id.clone(), sequence)?;
+
+        // Opaque ID from creator, sequence and time; the name never enters keys
+        let mut hasher = Sha256::new();
+        hasher.update(lawyer_id.as_bytes());
+        hasher.update(sequence.to_le_bytes());
+        hasher.update(time_now().to_le_bytes());
+        let case_id = hex::encode(&hasher.finalize()[..16]);
+        if self.cases.get(&case_id)?.is_some() {
+            return Err(CipherError::Conflict { reason: "case id already in use" });
+        }
+        // The full creator ID keeps numbers unique across creators
+        let matter_number = format!("M-{}-{:06}", lawyer_id, sequence);
+
+        // Set creator as admin
+        let mut members = vec![CaseMember {
+            member_id: lawyer_id.clone(),
+            role: CaseRole::Lawyer,
+            is_admin: true,
+            joined_at: time_now()
+        }];
+        if let Some(client_id) = params.client_id.filter(|id| !id.is_empty() && *id != lawyer_id) {
+            members.push(CaseMember {
+                member_id: client_id,
+                role: CaseRole::Client,
+                is_admin: false,
+                joined_at: time_now()
+            });
         }
-        let case_id = format!("{}_{}", params.case_name, env::time_now());
     
         let legal_case = LegalCase {
             case_id: case_id.clone(),
+            matter_number,
             case_name: params.case_name,
-            client_id: params.client_id.unwrap_or_default(),
-            lawyer_ids: vec![lawyer_id.clone()],
-            admin_id: lawyer_id,  // Set creator as admin
-            status: "active".to_string(),
+            members,
+            former_members: Vec::new(),
+            invitation_policy: params.invitation_policy.unwrap_or_default(),
+            pending_members: Vec::new(),
+            status: CaseStatus::Intake,     // the admin activates it once intake is done
+            status_changed_by: lawyer_id,
+            status_changed_at: time_now(),
             related_documents: params.initial_docs.unwrap_or_default(),
             privacy_level: params.privacy_level
         };
     
         self.cases.insert(case_id.clone(), legal_case)?;
         
-        app::emit!(CipherEvent::CaseOpened {
+        emit!(CipherEvent::CaseOpened {
             case_id: &case_id
         });
         
-        Ok(())
+        Ok(case_id)
     }
 
-    pub fn list_cases_for_user(&self, user_id: String) -> Result<Vec<LegalCase>, Error> {
+    pub fn list_cases_for_user(&self) -> Result<Vec<LegalCase>, CipherError> {
+        let user_id = caller_id();
         let cases: Vec<LegalCase> = self.cases.entries()?
-            .filter(|(_, case)| {
-                case.admin_id == user_id 
-                || case.lawyer_ids.contains(&user_id)
-                || case.client_id == user_id
-            })
+            .filter(|(_, case)| case.is_member(&user_id))
             .map(|(_, case)| case)
             .collect();
             
         Ok(cases)
     }
+
+    pub fn change_case_status(
+        &mut self,
+        case_id: String,
+        status: CaseStatus
+    ) -> Result<(), CipherError> {
+        let caller = caller_id();
+        let mut case = self.cases.get(&case_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+
+        if !case.is_admin(&caller) {
+            return Err(CipherError::Unauthorized { action: "change_case_status" });
+        }
+        if !case.status.can_transition_to(status) {
+            return Err(CipherError::Conflict { reason: "invalid case status transition" });
+        }
+
+        let previous = case.status;
+        case.status = status;
+        case.status_changed_by = caller.clone();
+        case.status_changed_at = time_now();
+        self.cases.insert(case_id.clone(), case)?;
+
+        emit!(CipherEvent::CaseStatusChanged {
+            case_id: &case_id,
+            from: &previous,
+            to: &status,
+            changed_by: &caller
+        });
+
+        Ok(())
+    }
+
+    pub fn close_case(&mut self, case_id: String) -> Result<(), CipherError> {
+        self.change_case_status(case_id, CaseStatus::Closed)
+    }
+
+    pub fn reopen_case(&mut self, case_id: String) -> Result<(), CipherError> {
+        self.change_case_status(case_id, CaseStatus::Active)
+    }
+
+    pub fn archive_case(&mut self, case_id: String) -> Result<(), CipherError> {
+        self.change_case_status(case_id, CaseStatus::Archived)
+    }
     // Add member to case
 pub fn add_case_member(
     &mut self,
-    caller_id: String,
     case_id: String,
     new_member_id: String,
-    role: String,
-) -> Result<(), Error> {
+    role: CaseRole,
+) -> Result<(), CipherError> {
+    let caller_id = caller_id();
     let mut case = self.cases.get(&case_id)?
-        .ok_or(Error::msg("Case not found"))?;
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
 
-    // Only existing members can add new members
-    if caller_id != case.admin_id && caller_id != case.client_id && !case.lawyer_ids.contains(&caller_id) {
-        return Err(Error::msg("Only case admin or case members can add new members"));
-    }
-    match role.as_str() {
-        "lawyer" => {
-            if !case.lawyer_ids.contains(&new_member_id) {
-                case.lawyer_ids.push(new_member_id.clone());
-            }
-        },
-        "client" => {
-            if case.client_id.is_empty() {
-                case.client_id = new_member_id.clone();
-            } else {
-                return Err(Error::msg("Case already has a client"));
-            }
-        },
-        _ => return Err(Error::msg("Invalid role specified"))
+    let direct = case.check_invitation_policy(&caller_id)?;
+    case.check_new_member(&new_member_id, role)?;
+
+    if !direct {
+        // Park the addition until an admin approves it
+        if case.pending_members.iter().any(|p| p.member_id == new_member_id) {
+            return Err(CipherError::Conflict { reason: "member already awaiting approval" });
+        }
+        case.pending_members.push(PendingMember {
+            member_id: new_member_id.clone(),
+            role,
+            requested_by: caller_id.clone(),
+            requested_at: time_now()
+        });
+        self.cases.insert(case_id.clone(), case)?;
+        emit!(CipherEvent::MemberApprovalRequested {
+            case_id: &case_id,
+            member_id: &new_member_id,
+            requested_by: &caller_id
+        });
+        return Ok(());
     }
+
+    case.pending_members.retain(|p| p.member_id != new_member_id);
+    case.members.push(CaseMember {
+        member_id: new_member_id.clone(),
+        role,
+        is_admin: false,
+        joined_at: time_now()
+    });
+    self.start_read_mark(&case, &new_member_id)?;
     self.cases.insert(case_id.clone(), case)?;
-    app::emit!(CipherEvent::MemberAdded {
+    emit!(CipherEvent::MemberAdded {
         case_id: &case_id,
         member_id: &new_member_id,
         role: &role
@@ -525,39 +2206,346 @@ pub fn add_case_member(
 
     Ok(())
 }
-    
-pub fn list_case_members(
-    &self,
+
+pub fn approve_case_member(
+    &mut self,
+    case_id: String,
+    member_id: String
+) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut case = self.cases.get(&case_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+
+    if !case.is_admin(&caller) {
+        return Err(CipherError::Unauthorized { action: "approve_case_member" });
+    }
+    let pending = case.pending_members.iter()
+        .position(|p| p.member_id == member_id)
+        .map(|index| case.pending_members.remove(index))
+        .ok_or_else(|| CipherError::NotFound { kind: "pending_member", id: member_id.clone() })?;
+    case.check_new_member(&member_id, pending.role)?;
+
+    case.members.push(CaseMember {
+        member_id: member_id.clone(),
+        role: pending.role,
+        is_admin: false,
+        joined_at: time_now()
+    });
+    self.start_read_mark(&case, &member_id)?;
+    self.cases.insert(case_id.clone(), case)?;
+    emit!(CipherEvent::MemberAdded {
+        case_id: &case_id,
+        member_id: &member_id,
+        role: &pending.role
+    });
+
+    Ok(())
+}
+
+pub fn reject_case_member(
+    &mut self,
+    case_id: String,
+    member_id: String
+) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut case = self.cases.get(&case_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+
+    if !case.is_admin(&caller) {
+        return Err(CipherError::Unauthorized { action: "reject_case_member" });
+    }
+    if !case.pending_members.iter().any(|p| p.member_id == member_id) {
+        return Err(CipherError::NotFound { kind: "pending_member", id: member_id });
+    }
+
+    case.pending_members.retain(|p| p.member_id != member_id);
+    self.cases.insert(case_id.clone(), case)?;
+    emit!(CipherEvent::MemberApprovalRejected {
+        case_id: &case_id,
+        member_id: &member_id
+    });
+
+    Ok(())
+}
+
+pub fn set_invitation_policy(
+    &mut self,
     case_id: String,
-    caller_id: String
-) -> Result<Vec<CaseMember>, Error> {
+    policy: InvitationPolicy
+) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut case = self.cases.get(&case_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+
+    if !case.is_admin(&caller) {
+        return Err(CipherError::Unauthorized { action: "set_invitation_policy" });
+    }
+
+    case.invitation_policy = policy;
+    self.cases.insert(case_id, case)?;
+    Ok(())
+}
+
+// Invitations
+pub fn invite_to_case(
+    &mut self,
+    case_id: String,
+    invitee_id: String,
+    role: CaseRole,
+    expires_at: u64
+) -> Result<String, CipherError> {
+    let caller = caller_id();
     let case = self.cases.get(&case_id)?
-        .ok_or(Error::msg("Case not found"))?;
-    let caller = caller_id.clone(); 
-    if !case.lawyer_ids.contains(&caller) 
-        && case.client_id != caller 
-        && case.admin_id != caller {
-        return Err(Error::msg("Unauthorized access"));
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+
+    // Invitations bypass approval, so only direct adders may send them
+    if !case.check_invitation_policy(&caller)? {
+        return Err(CipherError::Unauthorized { action: "invite_to_case" });
+    }
+    if !case.status.accepts_activity() {
+        return Err(CipherError::Conflict { reason: "case is closed" });
+    }
+    case.check_new_member(&invitee_id, role)?;
+    if expires_at <= time_now() {
+        return Err(CipherError::InvalidInput { field: "expires_at" });
     }
 
-    let mut members = Vec::new();
+    let invitation_id = format!("{}:{}", case_id, invitee_id);
+    if self.invitations.get(&invitation_id)?.map_or(false, |existing| existing.is_open()) {
+        return Err(CipherError::Conflict { reason: "invitation already pending" });
+    }
 
-    for lawyer_id in &case.lawyer_ids {
-        members.push(CaseMember {
-            member_id: lawyer_id.clone(),
-            role: "lawyer".to_string(),
-            is_admin: case.admin_id == *lawyer_id
-        });
+    let invitation = CaseInvitation {
+        invitation_id: invitation_id.clone(),
+        case_id: case_id.clone(),
+        invitee_id: invitee_id.clone(),
+        role,
+        invited_by: caller,
+        created_at: time_now(),
+        expires_at,
+        status: InvitationStatus::Pending
+    };
+    self.invitations.insert(invitation_id.clone(), invitation)?;
+
+    emit!(CipherEvent::InvitationSent {
+        invitation_id: &invitation_id,
+        case_id: &case_id,
+        invitee_id: &invitee_id,
+        role: &role
+    });
+
+    Ok(invitation_id)
+}
+
+pub fn accept_invitation(&mut self, invitation_id: String) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut invitation = self.invitations.get(&invitation_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "invitation", id: invitation_id.clone() })?;
+
+    if invitation.invitee_id != caller {
+        return Err(CipherError::Unauthorized { action: "accept_invitation" });
     }
-    if !case.client_id.is_empty() {
-        members.push(CaseMember {
-            member_id: case.client_id.clone(),
-            role: "client".to_string(),
-            is_admin: false
-        });
+    if invitation.status != InvitationStatus::Pending {
+        return Err(CipherError::Conflict { reason: "invitation already resolved" });
+    }
+    if !invitation.is_open() {
+        return Err(CipherError::Expired { what: "invitation" });
+    }
+
+    let case_id = invitation.case_id.clone();
+    let mut case = self.cases.get(&case_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+    // The invitation only stands while the inviter could still add the
+    // member directly and the case is still open
+    if !matches!(case.check_invitation_policy(&invitation.invited_by), Ok(true)) {
+        return Err(CipherError::Conflict { reason: "inviter may no longer add members" });
+    }
+    if !case.status.accepts_activity() {
+        return Err(CipherError::Conflict { reason: "case is closed" });
+    }
+    case.check_new_member(&caller, invitation.role)?;
+
+    case.pending_members.retain(|p| p.member_id != caller);
+    case.members.push(CaseMember {
+        member_id: caller.clone(),
+        role: invitation.role,
+        is_admin: false,
+        joined_at: time_now()
+    });
+    self.start_read_mark(&case, &caller)?;
+    self.cases.insert(case_id.clone(), case)?;
+
+    invitation.status = InvitationStatus::Accepted;
+    let role = invitation.role;
+    self.invitations.insert(invitation_id.clone(), invitation)?;
+
+    emit!(CipherEvent::InvitationAccepted {
+        invitation_id: &invitation_id,
+        case_id: &case_id,
+        invitee_id: &caller
+    });
+    emit!(CipherEvent::MemberAdded {
+        case_id: &case_id,
+        member_id: &caller,
+        role: &role
+    });
+
+    Ok(())
+}
+
+pub fn decline_invitation(&mut self, invitation_id: String) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut invitation = self.invitations.get(&invitation_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "invitation", id: invitation_id.clone() })?;
+
+    if invitation.invitee_id != caller {
+        return Err(CipherError::Unauthorized { action: "decline_invitation" });
+    }
+    if invitation.status != InvitationStatus::Pending {
+        return Err(CipherError::Conflict { reason: "invitation already resolved" });
+    }
+
+    invitation.status = InvitationStatus::Declined;
+    let case_id = invitation.case_id.clone();
+    self.invitations.insert(invitation_id.clone(), invitation)?;
+
+    emit!(CipherEvent::InvitationDeclined {
+        invitation_id: &invitation_id,
+        case_id: &case_id,
+        invitee_id: &caller
+    });
+
+    Ok(())
+}
+
+pub fn cancel_invitation(&mut self, invitation_id: String) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut invitation = self.invitations.get(&invitation_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "invitation", id: invitation_id.clone() })?;
+
+    if invitation.invited_by != caller {
+        return Err(CipherError::Unauthorized { action: "cancel_invitation" });
+    }
+    if invitation.status != InvitationStatus::Pending {
+        return Err(CipherError::Conflict { reason: "invitation already resolved" });
+    }
+
+    invitation.status = InvitationStatus::Cancelled;
+    let case_id = invitation.case_id.clone();
+    let invitee_id = invitation.invitee_id.clone();
+    self.invitations.insert(invitation_id.clone(), invitation)?;
+
+    emit!(CipherEvent::InvitationCancelled {
+        invitation_id: &invitation_id,
+        case_id: &case_id,
+        invitee_id: &invitee_id
+    });
+
+    Ok(())
+}
+
+pub fn list_pending_invitations(&self) -> Result<Vec<CaseInvitation>, CipherError> {
+    let caller = caller_id();
+    let invitations = self.invitations.entries()?
+        .filter(|(_, invitation)| invitation.invitee_id == caller && invitation.is_open())
+        .map(|(_, invitation)| invitation)
+        .collect();
+
+    Ok(invitations)
+}
+
+pub fn remove_case_member(
+    &mut self,
+    case_id: String,
+    member_id: String
+) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut case = self.cases.get(&case_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+
+    if !case.is_admin(&caller) {
+        return Err(CipherError::Unauthorized { action: "remove_case_member" });
+    }
+    // Admin rights have to be handed over before the last admin goes
+    if case.is_admin(&member_id) && case.admin_count() == 1 {
+        return Err(CipherError::Conflict { reason: "cannot remove the last case admin" });
     }
 
-    Ok(members)
+    self.detach_case_member(&mut case, &member_id)?;
+    self.cases.insert(case_id.clone(), case)?;
+
+    emit!(CipherEvent::MemberRemoved {
+        case_id: &case_id,
+        member_id: &member_id,
+        removed_by: &caller
+    });
+
+    Ok(())
+}
+
+pub fn leave_case(&mut self, case_id: String) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut case = self.cases.get(&case_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+
+    if case.is_admin(&caller) && case.admin_count() == 1 {
+        return Err(CipherError::Conflict { reason: "cannot remove the last case admin" });
+    }
+
+    self.detach_case_member(&mut case, &caller)?;
+    self.cases.insert(case_id.clone(), case)?;
+
+    emit!(CipherEvent::MemberRemoved {
+        case_id: &case_id,
+        member_id: &caller,
+        removed_by: &caller
+    });
+
+    Ok(())
+}
+
+pub fn transfer_case_admin(
+    &mut self,
+    case_id: String,
+    new_admin_id: String
+) -> Result<(), CipherError> {
+    let caller = caller_id();
+    let mut case = self.cases.get(&case_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+
+    if !case.is_admin(&caller) {
+        return Err(CipherError::Unauthorized { action: "transfer_case_admin" });
+    }
+    if !case.member(&new_admin_id).map_or(false, |m| m.role.is_counsel()) {
+        return Err(CipherError::InvalidInput { field: "new_admin_id" });
+    }
+
+    for member in case.members.iter_mut() {
+        member.is_admin = member.member_id == new_admin_id;
+    }
+    self.cases.insert(case_id.clone(), case)?;
+
+    emit!(CipherEvent::AdminTransferred {
+        case_id: &case_id,
+        from: &caller,
+        to: &new_admin_id
+    });
+
+    Ok(())
+}
+
+pub fn list_case_members(
+    &self,
+    case_id: String
+) -> Result<Vec<CaseMember>, CipherError> {
+    let case = self.cases.get(&case_id)?
+        .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
+    let caller = caller_id();
+    if !case.is_member(&caller) {
+        return Err(CipherError::Unauthorized { action: "list_case_members" });
+    }
+
+    Ok(case.members)
 }
 
 // Vault Operations
@@ -566,66 +2554,122 @@ pub fn store_document(
     encrypted_content: Vec<u8>,
     doc_hash: String, 
     document_type: String,
-) -> Result<(), Error> {
-    let owner = env::executor_id();
-    let owner_id = String::from_utf8_lossy(&owner).to_string();
-    let doc_hash_clone = doc_hash.clone();
-
-    let document = LegalDocument {
-        encrypted_content,
-        document_hash: doc_hash.clone(),
-        document_type: document_type.clone(),
-        owner_id: owner_id.clone(),
-        case_id: None,
-        access_list: vec![owner_id.clone()],
-        ai_analysis_id: None,
-        timestamp: env::time_now(),
-    };
+) -> Result<(), CipherError> {
+    let owner_id = caller_id();
+    let doc_hash = verify_content_hash(&encrypted_content, &doc_hash)?;
+    self.check_document_target(&owner_id, &doc_hash, None, None)?;
 
-    self.documents.insert(doc_hash, document)?;
-    
-    app::emit!(CipherEvent::DocumentUploaded {
-        doc_hash: &doc_hash_clone,
-        doc_type: &document_type
-    });
-    
-    Ok(())
+    let chunk_hashes = self.store_chunks(&encrypted_content)?;
+    self.insert_document(&owner_id, doc_hash, document_type, None, chunk_hashes, encrypted_content.len() as u64)
 }
 
 pub fn grant_access(
     &mut self,
     doc_hash: String,
     grantee_id: String,
-    caller_id: String
-) -> Result<(), Error> {
-    let caller = caller_id.clone();
-    let owner_id = caller;
+    level: AccessLevel,
+    expires_at: Option<u64>
+) -> Result<(), CipherError> {
+    let granter_id = caller_id();
 
     let mut doc = self.documents.get(&doc_hash)?
-        .ok_or(Error::msg("Document not found"))?;
+        .ok_or_else(|| CipherError::NotFound { kind: "document", id: doc_hash.clone() })?;
 
-    // Only the owner can grant access
-    if doc.owner_id != owner_id {
-        return Err(Error::msg("Only document owner can grant access"));
+    // Only the owner, or a grantee holding share access and consent, can grant access
+    if doc.owner_id != granter_id
+        && !(doc.has_access(&granter_id, AccessLevel::Share) && self.consent_allows(&doc, &granter_id, "share")?)
+    {
+        return Err(CipherError::Unauthorized { action: "grant_access" });
+    }
+    if grantee_id.is_empty() || grantee_id == doc.owner_id {
+        return Err(CipherError::InvalidInput { field: "grantee_id" });
+    }
+    let now = time_now();
+    if expires_at.map_or(false, |expires_at| expires_at <= now) {
+        return Err(CipherError::InvalidInput { field: "expires_at" });
     }
 
-    if !doc.access_list.contains(&grantee_id) {
-        doc.access_list.push(grantee_id);
+    // Delegated access never outranks or outlives the granter's own grant,
+    // and only the owner may replace a grant someone else issued
+    let mut level = level;
+    let mut expires_at = expires_at;
+    if doc.owner_id != granter_id {
+        let own = doc.live_grants()
+            .find(|grant| grant.grantee_id == granter_id)
+            .cloned()
+            .ok_or(CipherError::Unauthorized { action: "grant_access" })?;
+        if level > own.level {
+            level = own.level;
+        }
+        if let Some(own_expiry) = own.expires_at {
+            expires_at = Some(expires_at.map_or(own_expiry, |requested| requested.min(own_expiry)));
+        }
+        if doc.live_grants().any(|grant| grant.grantee_id == grantee_id && grant.granted_by != granter_id) {
+            return Err(CipherError::Unauthorized { action: "grant_access" });
+        }
     }
 
-    self.documents.insert(doc_hash, doc)?;
+    // A new grant replaces the grantee's previous one
+    doc.grants.retain(|grant| grant.grantee_id != grantee_id);
+    doc.grants.push(AccessGrant {
+        grantee_id: grantee_id.clone(),
+        level,
+        granted_by: granter_id.clone(),
+        granted_at: now,
+        expires_at,
+    });
+    self.sync_chain_access(&doc.root_hash, &doc.grants)?;
+
+    emit!(CipherEvent::AccessGranted {
+        doc_hash: &doc_hash,
+        grantee_id: &grantee_id,
+        level: &level,
+        granted_by: &granter_id
+    });
     Ok(())
 }
 
-pub fn get_accessible_documents(&self, caller_id: String) -> Result<Vec<LegalDocument>, Error> {
-    let caller = caller_id.clone();
+// The owner can revoke any grant; other members only the grants they issued
+pub fn revoke_access(
+    &mut self,
+    doc_hash: String,
+    grantee_id: String
+) -> Result<(), CipherError> {
+    let revoker_id = caller_id();
+
+    let mut doc = self.documents.get(&doc_hash)?
+        .ok_or_else(|| CipherError::NotFound { kind: "document", id: doc_hash.clone() })?;
+    let grant = doc.grants.iter()
+        .find(|grant| grant.grantee_id == grantee_id)
+        .ok_or_else(|| CipherError::NotFound { kind: "access_grant", id: grantee_id.clone() })?;
+    if doc.owner_id != revoker_id && grant.granted_by != revoker_id {
+        return Err(CipherError::Unauthorized { action: "revoke_access" });
+    }
+
+    doc.grants.retain(|grant| grant.grantee_id != grantee_id);
+    self.sync_chain_access(&doc.root_hash, &doc.grants)?;
+
+    emit!(CipherEvent::AccessRevoked {
+        doc_hash: &doc_hash,
+        grantee_id: &grantee_id,
+        revoked_by: &revoker_id
+    });
+    Ok(())
+}
+
+pub fn get_accessible_documents(&self) -> Result<Vec<DocumentSummary>, CipherError> {
+    let caller = caller_id();
     
-    let accessible_docs = self.documents.entries()?
-        .filter(|(_, doc)| {
-            doc.owner_id == caller || doc.access_list.contains(&caller)
-        })
-        .map(|(_, doc)| doc)
-        .collect();
+    let mut accessible_docs = Vec::new();
+    for (_, doc) in self.documents.entries()? {
+        // Older versions are reachable through list_document_versions
+        if doc.superseded_by.is_none()
+            && doc.has_access(&caller, AccessLevel::Read)
+            && self.consent_allows(&doc, &caller, "read")?
+        {
+            accessible_docs.push(self.summarize_document(&doc, &caller)?);
+        }
+    }
         
     Ok(accessible_docs)
 }
@@ -636,94 +2680,365 @@ pub fn get_accessible_documents(&self, caller_id: String) -> Result<Vec<LegalDoc
         encrypted_content: Vec<u8>,
         doc_hash: String, 
         document_type: String,
-        case_id: String,
-        caller_id: String
-    ) -> Result<(), Error> {
-        let owner_id = caller_id.clone();
-    
-      
-        let mut case = self.cases.get(&case_id)?
-            .ok_or(Error::msg("Case not found"))?;
+        case_id: String
+    ) -> Result<(), CipherError> {
+        let owner_id = caller_id();
+        let doc_hash = verify_content_hash(&encrypted_content, &doc_hash)?;
+        self.check_document_target(&owner_id, &doc_hash, Some(&case_id), None)?;
 
-        let members = self.list_case_members(case_id.clone(), owner_id.clone())?;
-        if !members.iter().any(|m| m.member_id == owner_id) {
-            return Err(Error::msg("Unauthorized to upload document for this case"));
-        }
-    
-        let document = LegalDocument {
-            encrypted_content,
-            document_hash: doc_hash.clone(),
-            document_type: document_type.clone(),  // e.g., "image/png"
-            owner_id: owner_id.clone(),
-            case_id: Some(case_id.clone()),
-            access_list: vec![owner_id.clone()],
-            ai_analysis_id: None,
-            timestamp: env::time_now(),
-        };
-    
-        let doc_hash_clone = doc_hash.clone();
-        self.documents.insert(doc_hash, document)?;
-    
-    
-        if !case.related_documents.contains(&doc_hash_clone) {
-            case.related_documents.push(doc_hash_clone.clone());
-            self.cases.insert(case_id, case)?;
-        }
-    
-        app::emit!(CipherEvent::DocumentUploaded {
-            doc_hash: &doc_hash_clone,
-            doc_type: &document_type
-        });
-    
-        Ok(())
+        let chunk_hashes = self.store_chunks(&encrypted_content)?;
+        self.insert_document(&owner_id, doc_hash, document_type, Some(case_id), chunk_hashes, encrypted_content.len() as u64)
     }
     
    
-    pub fn list_case_documents(&self, case_id: String, caller_id: String) -> Result<Vec<LegalDocument>, Error> {
-        let caller = caller_id.clone();
+    pub fn list_case_documents(&self, case_id: String) -> Result<Vec<DocumentSummary>, CipherError> {
+        let caller = caller_id();
     
      
         let case = self.cases.get(&case_id)?
-            .ok_or(Error::msg("Case not found"))?;
+            .ok_or_else(|| CipherError::NotFound { kind: "case", id: case_id.clone() })?;
     
      
-        let members = self.list_case_members(case_id.clone(), caller.clone())?;
-        if !members.iter().any(|m| m.member_id == caller) {
-            return Err(Error::msg("Unauthorized access to case documents"));
-        }
+        case.require(&caller, CasePermission::ViewDocuments)?;
     
+        // related_documents can name hashes attached at open_case, so each
+        // document must belong to this case and be readable by the caller
         let mut docs = Vec::new();
         for doc_hash in &case.related_documents {
             if let Some(doc) = self.documents.get(doc_hash)? {
-                docs.push(doc.clone());
+                if doc.case_id.as_deref() == Some(case_id.as_str()) && self.can_read_document(&doc, &caller)? {
+                    docs.push(self.summarize_document(&doc, &caller)?);
+                }
             }
         }
         Ok(docs)
     }
+
+    // Full content of one document, reassembled from its chunks. Large
+    // documents are better streamed with get_document_chunk.
+    pub fn get_document_content(&self, doc_hash: String) -> Result<Vec<u8>, CipherError> {
+        let caller = caller_id();
+        let doc = self.documents.get(&doc_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "document", id: doc_hash.clone() })?;
+        if !self.can_read_document(&doc, &caller)? {
+            return Err(CipherError::Unauthorized { action: "get_document_content" });
+        }
+
+        let mut content = Vec::with_capacity(doc.size as usize);
+        for chunk_hash in &doc.chunk_hashes {
+            let chunk = self.chunks.get(chunk_hash)?
+                .ok_or_else(|| CipherError::NotFound { kind: "chunk", id: chunk_hash.clone() })?;
+            content.extend_from_slice(&chunk);
+        }
+        Ok(content)
+    }
+
+    // Uploads a new draft on top of the latest version of a document
+    pub fn upload_document_version(
+        &mut self,
+        parent_hash: String,
+        encrypted_content: Vec<u8>,
+        doc_hash: String,
+        change_note: Option<String>
+    ) -> Result<u32, CipherError> {
+        let author_id = caller_id();
+        let doc_hash = verify_content_hash(&encrypted_content, &doc_hash)?;
+        self.check_document_target(&author_id, &doc_hash, None, Some(&parent_hash))?;
+
+        let chunk_hashes = self.store_chunks(&encrypted_content)?;
+        self.insert_document_version(&author_id, parent_hash, doc_hash, change_note, chunk_hashes, encrypted_content.len() as u64)
+    }
+
+    // Revision history of the chain that doc_hash belongs to, oldest first
+    pub fn list_document_versions(&self, doc_hash: String) -> Result<Vec<DocumentVersion>, CipherError> {
+        let caller = caller_id();
+        let doc = self.documents.get(&doc_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "document", id: doc_hash.clone() })?;
+        if !self.can_read_document(&doc, &caller)? {
+            return Err(CipherError::Unauthorized { action: "list_document_versions" });
+        }
+
+        let mut versions = Vec::new();
+        for hash in self.document_versions.get(&doc.root_hash)?.unwrap_or_default() {
+            if let Some(version) = self.documents.get(&hash)? {
+                versions.push(version.version_info());
+            }
+        }
+        Ok(versions)
+    }
+
+    pub fn get_document_version(&self, doc_hash: String, version: u32) -> Result<LegalDocument, CipherError> {
+        let caller = caller_id();
+        let doc = self.documents.get(&doc_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "document", id: doc_hash.clone() })?;
+        if !self.can_read_document(&doc, &caller)? {
+            return Err(CipherError::Unauthorized { action: "get_document_version" });
+        }
+
+        let chain = self.document_versions.get(&doc.root_hash)?.unwrap_or_default();
+        let version_hash = version.checked_sub(1)
+            .and_then(|index| chain.get(index as usize))
+            .ok_or_else(|| CipherError::NotFound { kind: "document_version", id: format!("{}:{}", doc.root_hash, version) })?;
+        self.documents.get(version_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "document", id: version_hash.clone() })
+    }
+
+    // Chunked upload for large documents. begin_upload declares the final
+    // hash and size and where the document goes (vault, case_id, or a new
+    // version of parent_hash); chunks may then arrive in any order.
+    pub fn begin_upload(
+        &mut self,
+        doc_hash: String,
+        document_type: String,
+        size: u64,
+        case_id: Option<String>,
+        parent_hash: Option<String>,
+        change_note: Option<String>
+    ) -> Result<String, CipherError> {
+        let uploader_id = caller_id();
+        let doc_hash = doc_hash.to_ascii_lowercase();
+        if doc_hash.len() != 64 || !doc_hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(CipherError::InvalidInput { field: "doc_hash" });
+        }
+        if size == 0 || size > MAX_DOCUMENT_SIZE {
+            return Err(CipherError::InvalidInput { field: "size" });
+        }
+        self.check_document_target(&uploader_id, &doc_hash, case_id.as_deref(), parent_hash.as_deref())?;
+
+        let now = time_now();
+        let mut hasher = Sha256::new();
+        hasher.update(uploader_id.as_bytes());
+        hasher.update(doc_hash.as_bytes());
+        hasher.update(now.to_le_bytes());
+        let upload_id = hex::encode(&hasher.finalize()[..16]);
+
+        let session = UploadSession {
+            upload_id: upload_id.clone(),
+            uploader_id,
+            doc_hash,
+            document_type,
+            size,
+            case_id,
+            parent_hash,
+            change_note,
+            chunk_hashes: vec![None; chunk_count(size)],
+            started_at: now,
+            expires_at: now + UPLOAD_IDLE_TIMEOUT,
+        };
+        self.uploads.insert(upload_id.clone(), session)?;
+        Ok(upload_id)
+    }
+
+    // Stores chunk `index` of an upload and returns its hash. Every chunk
+    // but the last is exactly MAX_CHUNK_SIZE bytes.
+    pub fn upload_chunk(&mut self, upload_id: String, index: u32, data: Vec<u8>) -> Result<String, CipherError> {
+        let uploader_id = caller_id();
+        let mut session = self.uploads.get(&upload_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "upload", id: upload_id.clone() })?;
+        if session.uploader_id != uploader_id {
+            return Err(CipherError::Unauthorized { action: "upload_chunk" });
+        }
+        let now = time_now();
+        if session.is_expired(now) {
+            return Err(CipherError::Expired { what: "upload" });
+        }
+
+        let slot = index as usize;
+        if slot >= session.chunk_hashes.len() {
+            return Err(CipherError::InvalidInput { field: "index" });
+        }
+        let expected_len = if slot + 1 == session.chunk_hashes.len() {
+            session.size as usize - slot * MAX_CHUNK_SIZE
+        } else {
+            MAX_CHUNK_SIZE
+        };
+        if data.len() != expected_len {
+            return Err(CipherError::InvalidInput { field: "data" });
+        }
+
+        let chunk_hash = self.store_chunk(&data)?;
+        let replaced = session.chunk_hashes[slot].replace(chunk_hash.clone())
+            .filter(|previous| *previous != chunk_hash);
+        session.expires_at = now + UPLOAD_IDLE_TIMEOUT;
+        self.uploads.insert(upload_id, session)?;
+        if let Some(previous) = replaced {
+            self.release_chunks(vec![previous])?;
+        }
+        Ok(chunk_hash)
+    }
+
+    // Checks the assembled content against the declared hash and creates the
+    // document. Permissions are checked again since they may have changed.
+    pub fn finalize_upload(&mut self, upload_id: String) -> Result<String, CipherError> {
+        let uploader_id = caller_id();
+        let session = self.uploads.get(&upload_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "upload", id: upload_id.clone() })?;
+        if session.uploader_id != uploader_id {
+            return Err(CipherError::Unauthorized { action: "finalize_upload" });
+        }
+        if session.is_expired(time_now()) {
+            return Err(CipherError::Expired { what: "upload" });
+        }
+
+        let chunk_hashes: Vec<String> = session.chunk_hashes.iter()
+            .cloned()
+            .collect::<Option<_>>()
+            .ok_or(CipherError::Conflict { reason: "upload is missing chunks" })?;
+
+        let mut hasher = Sha256::new();
+        for chunk_hash in &chunk_hashes {
+            let chunk = self.chunks.get(chunk_hash)?
+                .ok_or_else(|| CipherError::NotFound { kind: "chunk", id: chunk_hash.clone() })?;
+            hasher.update(&chunk);
+        }
+        if hex::encode(hasher.finalize()) != session.doc_hash {
+            return Err(CipherError::InvalidInput { field: "doc_hash" });
+        }
+
+        self.check_document_target(&uploader_id, &session.doc_hash, session.case_id.as_deref(), session.parent_hash.as_deref())?;
+        self.uploads.remove(&upload_id)?;
+
+        let doc_hash = session.doc_hash.clone();
+        match session.parent_hash {
+            Some(parent_hash) => {
+                self.insert_document_version(&uploader_id, parent_hash, session.doc_hash, session.change_note, chunk_hashes, session.size)?;
+            },
+            None => {
+                self.insert_document(&uploader_id, session.doc_hash, session.document_type, session.case_id, chunk_hashes, session.size)?;
+            },
+        }
+        Ok(doc_hash)
+    }
+
+    // Drops an unfinished upload and whatever chunks only it was holding
+    pub fn abort_upload(&mut self, upload_id: String) -> Result<(), CipherError> {
+        let uploader_id = caller_id();
+        let session = self.uploads.get(&upload_id)?
+            .ok_or_else(|| CipherError::NotFound { kind: "upload", id: upload_id.clone() })?;
+        if session.uploader_id != uploader_id {
+            return Err(CipherError::Unauthorized { action: "abort_upload" });
+        }
+
+        self.uploads.remove(&upload_id)?;
+        self.release_chunks(session.chunk_hashes.into_iter().flatten().collect())
+    }
+
+    // Maintenance: any caller may drop expired uploads and their chunks
+    pub fn purge_expired_uploads(&mut self) -> Result<u64, CipherError> {
+        let now = time_now();
+        let mut released = Vec::new();
+        let mut purged = 0;
+        let expired: Vec<_> = self.uploads.entries()?
+            .filter(|(_, session)| session.is_expired(now))
+            .collect();
+        for (upload_id, session) in expired {
+            self.uploads.remove(&upload_id)?;
+            released.extend(session.chunk_hashes.into_iter().flatten());
+            purged += 1;
+        }
+        self.release_chunks(released)?;
+        Ok(purged)
+    }
+
+    // Streams a document one chunk at a time; index runs to chunk_count - 1
+    pub fn get_document_chunk(&self, doc_hash: String, index: u32) -> Result<DocumentChunk, CipherError> {
+        let caller = caller_id();
+        let doc = self.documents.get(&doc_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "document", id: doc_hash.clone() })?;
+        if !self.can_read_document(&doc, &caller)? {
+            return Err(CipherError::Unauthorized { action: "get_document_chunk" });
+        }
+
+        let chunk_hash = doc.chunk_hashes.get(index as usize)
+            .ok_or_else(|| CipherError::NotFound { kind: "document_chunk", id: format!("{}:{}", doc_hash, index) })?
+            .clone();
+        let data = self.chunks.get(&chunk_hash)?
+            .ok_or_else(|| CipherError::NotFound { kind: "chunk", id: chunk_hash.clone() })?;
+
+        Ok(DocumentChunk {
+            doc_hash,
+            index,
+            chunk_count: doc.chunk_hashes.len() as u32,
+            chunk_hash,
+            data,
+        })
+    }
     // Consent Management
+    pub fn grant_consent(
+        &mut self,
+        consent: LegalConsent
+    ) -> Result<(), CipherError> {
+        let client_id = caller_id();
+
+        // Only the client can authorise a lawyer, with their own key.
+        if consent.client_id != client_id {
+            return Err(CipherError::Unauthorized { action: "grant_consent" });
+        }
+        if bs58::encode(&consent.public_key).into_string() != client_id {
+            return Err(CipherError::InvalidInput { field: "public_key" });
+        }
+        if consent.lawyer_id.is_empty() || consent.lawyer_id == client_id {
+            return Err(CipherError::InvalidInput { field: "lawyer_id" });
+        }
+        if scope_rank(&consent.scope).is_none() {
+            return Err(CipherError::InvalidInput { field: "scope" });
+        }
+        if consent.expiration <= time_now() {
+            return Err(CipherError::Expired { what: "consent" });
+        }
+
+        consent.verify_signature()?;
+
+        let consent_key = format!("{}:{}", client_id, consent.lawyer_id);
+        let lawyer_id = consent.lawyer_id.clone();
+        self.consents.insert(consent_key, consent)?;
+
+        emit!(CipherEvent::ConsentGranted {
+            client_id: &client_id,
+            lawyer_id: &lawyer_id
+        });
+
+        Ok(())
+    }
+
+    pub fn get_consent(
+        &self,
+        client_id: String,
+        lawyer_id: String
+    ) -> Result<LegalConsent, CipherError> {
+        let caller = caller_id();
+        if caller != client_id && caller != lawyer_id {
+            return Err(CipherError::Unauthorized { action: "get_consent" });
+        }
+
+        let consent_key = format!("{}:{}", client_id, lawyer_id);
+        self.consents.get(&consent_key)?
+            .ok_or_else(|| CipherError::NotFound { kind: "consent", id: consent_key.clone() })
+    }
+
+    pub fn list_consents(&self) -> Result<Vec<LegalConsent>, CipherError> {
+        let caller = caller_id();
+
+        let consents = self.consents.entries()?
+            .filter(|(_, consent)| consent.client_id == caller || consent.lawyer_id == caller)
+            .map(|(_, consent)| consent)
+            .collect();
+
+        Ok(consents)
+    }
+
     pub fn revoke_consent(
         &mut self,
         lawyer_id: String
-    ) -> Result<(), Error> {
-        let client_id = String::from_utf8_lossy(&env::executor_id()).to_string();
+    ) -> Result<(), CipherError> {
+        let client_id = caller_id();
         let consent_key = format!("{}:{}", client_id, lawyer_id);
 
+        // Reads are consent-gated, so dropping the consent cuts off access
+        // without touching document grants.
         self.consents.remove(&consent_key)?;
         
-        // Remove from all access lists
-        let updates: Vec<_> = self.documents.entries()?
-            .filter(|(_, doc)| doc.owner_id == client_id)
-            .map(|(hash, mut doc)| {
-                doc.access_list.retain(|id| id != &lawyer_id);
-                (hash, doc)
-            })
-            .collect();
-            
-        for (doc_hash, doc) in updates {
-            self.documents.insert(doc_hash, doc)?;
-        }
-        
-        app::emit!(CipherEvent::ConsentRevoked {
+        emit!(CipherEvent::ConsentRevoked {
             client_id: &client_id,
             lawyer_id: &lawyer_id
         });
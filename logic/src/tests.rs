@@ -0,0 +1,1058 @@
+use super::*;
+use ed25519_dalek::{Signer, SigningKey};
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+// Stand-ins for the executor identity and host clock
+thread_local! {
+    static CALLER: RefCell<String> = const { RefCell::new(String::new()) };
+    static NOW: Cell<u64> = const { Cell::new(1_000_000_000) };
+}
+
+pub(crate) fn caller_id() -> String {
+    CALLER.with(|caller| caller.borrow().clone())
+}
+
+pub(crate) fn time_now() -> u64 {
+    NOW.with(Cell::get)
+}
+
+fn act_as(member_id: &str) {
+    CALLER.with(|caller| *caller.borrow_mut() = member_id.to_string());
+}
+
+fn advance(nanos: u64) {
+    NOW.with(|now| now.set(now.get() + nanos));
+}
+
+// Executor identities are the bs58 form of an ed25519 public key
+fn identity(seed: u8) -> (SigningKey, String) {
+    let key = SigningKey::from_bytes(&[seed; 32]);
+    let id = bs58::encode(key.verifying_key().to_bytes()).into_string();
+    (key, id)
+}
+
+fn signed_consent(key: &SigningKey, client_id: &str, lawyer_id: &str, scope: &str) -> LegalConsent {
+    let mut consent = LegalConsent {
+        client_id: client_id.to_string(),
+        lawyer_id: lawyer_id.to_string(),
+        scope: scope.to_string(),
+        expiration: time_now() + 3_600_000_000_000,
+        public_key: key.verifying_key().to_bytes().to_vec(),
+        ..Default::default()
+    };
+    consent.signed_message = consent.canonical_message();
+    consent.signature = key.sign(&consent.signed_message).to_bytes().to_vec();
+    consent
+}
+
+fn open_case(state: &mut CipherState, lawyer: &str, client: Option<&str>) -> String {
+    act_as(lawyer);
+    state.open_case(CaseCreateParams {
+        case_name: "Doe v. Roe".to_string(),
+        description: "Contract dispute".to_string(),
+        client_id: client.map(str::to_string),
+        privacy_level: PrivacyLevel::Private,
+        initial_docs: None,
+        invitation_policy: None,
+    }).unwrap()
+}
+
+fn send(state: &mut CipherState, sender: &str, case_id: &str, text: &str) -> String {
+    act_as(sender);
+    state.send_message(case_id.to_string(), text.as_bytes().to_vec(), vec![0; 12], MessageMode::Persistent, None, None)
+        .unwrap()
+}
+
+#[test]
+fn user_details_cannot_be_written_for_another_identity() {
+    let mut state = CipherState::init();
+    act_as("alice");
+    state.update_user_details(user_details { user_id: "alice".to_string(), name: Some("Alice".to_string()), ..Default::default() })
+        .unwrap();
+
+    act_as("mallory");
+    let result = state.update_user_details(user_details { user_id: "alice".to_string(), name: Some("Mallory".to_string()), ..Default::default() });
+    assert!(matches!(result, Err(CipherError::Unauthorized { action: "update_user_details" })));
+
+    act_as("alice");
+    assert_eq!(state.get_user_details("alice".to_string()).unwrap().name.as_deref(), Some("Alice"));
+    act_as("mallory");
+    assert!(matches!(state.get_user_details("alice".to_string()), Err(CipherError::Unauthorized { .. })));
+}
+
+#[test]
+fn consent_cannot_be_granted_on_behalf_of_another_client() {
+    let mut state = CipherState::init();
+    let (client_key, client) = identity(1);
+    let (mallory_key, mallory) = identity(2);
+
+    // Mallory cannot claim to be the client, even with a valid signature of her own
+    act_as(&mallory);
+    let forged = signed_consent(&mallory_key, &client, "lawyer", "read");
+    assert!(matches!(state.grant_consent(forged), Err(CipherError::Unauthorized { action: "grant_consent" })));
+
+    // Nor replay a consent the client signed
+    let genuine = signed_consent(&client_key, &client, "lawyer", "read");
+    assert!(matches!(state.grant_consent(genuine.clone()), Err(CipherError::Unauthorized { .. })));
+
+    act_as(&client);
+    state.grant_consent(genuine).unwrap();
+
+    // Revoking only ever touches the caller's own consents
+    act_as(&mallory);
+    state.revoke_consent("lawyer".to_string()).unwrap();
+    act_as(&client);
+    assert!(state.get_consent(client.clone(), "lawyer".to_string()).is_ok());
+}
+
+#[test]
+fn consent_must_be_signed_with_the_callers_key() {
+    let mut state = CipherState::init();
+    let (_, client) = identity(1);
+    let (mallory_key, _) = identity(2);
+
+    act_as(&client);
+    let consent = signed_consent(&mallory_key, &client, "lawyer", "read");
+    assert!(matches!(state.grant_consent(consent), Err(CipherError::InvalidInput { field: "public_key" })));
+}
+
+#[test]
+fn case_admin_actions_require_the_admin_identity() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+
+    act_as("client");
+    assert!(matches!(state.close_case(case_id.clone()), Err(CipherError::Unauthorized { action: "change_case_status" })));
+    assert!(matches!(
+        state.transfer_case_admin(case_id.clone(), "client".to_string()),
+        Err(CipherError::Unauthorized { action: "transfer_case_admin" })
+    ));
+    assert!(matches!(
+        state.remove_case_member(case_id.clone(), "lawyer".to_string()),
+        Err(CipherError::Unauthorized { action: "remove_case_member" })
+    ));
+
+    act_as("lawyer");
+    state.close_case(case_id).unwrap();
+}
+
+#[test]
+fn messages_cannot_be_edited_or_retracted_by_another_member() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    let message_id = send(&mut state, "lawyer", &case_id, "privileged");
+
+    act_as("client");
+    assert!(matches!(
+        state.edit_message(case_id.clone(), message_id.clone(), b"forged".to_vec(), vec![0; 12]),
+        Err(CipherError::Unauthorized { action: "edit_message" })
+    ));
+    assert!(matches!(
+        state.retract_message(case_id.clone(), message_id.clone()),
+        Err(CipherError::Unauthorized { action: "retract_message" })
+    ));
+
+    let messages = state.get_case_messages(case_id).unwrap();
+    assert_eq!(messages[0].ciphertext, b"privileged".to_vec());
+    assert!(messages[0].edited_at.is_none());
+}
+
+#[test]
+fn invitations_can_only_be_accepted_by_the_invitee() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", None);
+    let invitation_id = state.invite_to_case(case_id.clone(), "expert".to_string(), CaseRole::Expert, time_now() + 1_000_000_000)
+        .unwrap();
+
+    act_as("mallory");
+    assert!(matches!(state.accept_invitation(invitation_id.clone()), Err(CipherError::Unauthorized { action: "accept_invitation" })));
+    assert!(matches!(state.cancel_invitation(invitation_id.clone()), Err(CipherError::Unauthorized { action: "cancel_invitation" })));
+
+    act_as("expert");
+    state.accept_invitation(invitation_id).unwrap();
+    assert!(state.list_case_members(case_id).unwrap().iter().any(|m| m.member_id == "expert"));
+}
+
+#[test]
+fn ai_results_are_only_accepted_from_the_requested_executor() {
+    let mut state = CipherState::init();
+    let doc_hash = store_vault_document(&mut state, "owner", "engagement letter");
+
+    act_as("mallory");
+    assert!(matches!(
+        state.request_ai_analysis(doc_hash.clone(), "mallory".to_string()),
+        Err(CipherError::Unauthorized { action: "request_ai_analysis" })
+    ));
+
+    act_as("owner");
+    let analysis_id = state.request_ai_analysis(doc_hash.clone(), "ai-canister".to_string()).unwrap();
+
+    act_as("mallory");
+    let forged = state.update_ai_analysis(analysis_id.clone(), "all clear".to_string(), 0, Vec::new());
+    assert!(matches!(forged, Err(CipherError::Unauthorized { action: "update_ai_analysis" })));
+
+    act_as("ai-canister");
+    advance(1_000);
+    state.update_ai_analysis(analysis_id.clone(), "two risky clauses".to_string(), 2, vec!["renegotiate".to_string()])
+        .unwrap();
+    let result = state.ai_results.get(&analysis_id).unwrap().unwrap();
+    assert_eq!(result.summary, "two risky clauses");
+    assert_eq!(result.generated_by, "ai-canister");
+}
+
+fn store_vault_document(state: &mut CipherState, owner: &str, text: &str) -> String {
+    let content = text.as_bytes().to_vec();
+    let doc_hash = hex::encode(Sha256::digest(&content));
+    act_as(owner);
+    state.store_document(content, doc_hash.clone(), "text/plain".to_string()).unwrap();
+    doc_hash
+}
+
+#[test]
+fn vault_grantees_outside_a_client_relationship_need_no_consent() {
+    let mut state = CipherState::init();
+    let doc_hash = store_vault_document(&mut state, "owner", "shared notes");
+    state.grant_access(doc_hash.clone(), "colleague".to_string(), AccessLevel::Read, None).unwrap();
+
+    act_as("colleague");
+    assert_eq!(state.get_document_content(doc_hash).unwrap(), b"shared notes".to_vec());
+}
+
+#[test]
+fn counsel_read_a_clients_vault_document_on_live_consent() {
+    let mut state = CipherState::init();
+    let (client_key, client) = identity(1);
+    open_case(&mut state, "lawyer", Some(&client));
+    let doc_hash = store_vault_document(&mut state, &client, "medical records");
+    state.grant_access(doc_hash.clone(), "lawyer".to_string(), AccessLevel::Read, None).unwrap();
+
+    act_as("lawyer");
+    assert!(matches!(state.get_document_content(doc_hash.clone()), Err(CipherError::Unauthorized { .. })));
+
+    act_as(&client);
+    state.grant_consent(signed_consent(&client_key, &client, "lawyer", "read")).unwrap();
+    act_as("lawyer");
+    assert!(state.get_document_content(doc_hash.clone()).is_ok());
+
+    act_as(&client);
+    state.revoke_consent("lawyer".to_string()).unwrap();
+    act_as("lawyer");
+    assert!(matches!(state.get_document_content(doc_hash), Err(CipherError::Unauthorized { .. })));
+}
+
+#[test]
+fn counsel_read_case_messages_on_live_consent() {
+    let mut state = CipherState::init();
+    let (client_key, client) = identity(1);
+    let case_id = open_case(&mut state, "lawyer", Some(&client));
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+    send(&mut state, &client, &case_id, "the contract is attached");
+
+    act_as("lawyer");
+    assert!(matches!(state.get_case_messages(case_id.clone()), Err(CipherError::ConsentRequired { scope: "read" })));
+    assert!(matches!(
+        state.get_case_messages_page(case_id.clone(), None, None, 10),
+        Err(CipherError::ConsentRequired { .. })
+    ));
+
+    // Consent is about counsel; other members read on their membership
+    act_as("paralegal");
+    assert_eq!(state.get_case_messages(case_id.clone()).unwrap().len(), 1);
+
+    act_as(&client);
+    state.grant_consent(signed_consent(&client_key, &client, "lawyer", "read")).unwrap();
+    act_as("lawyer");
+    assert_eq!(state.get_case_messages(case_id).unwrap().len(), 1);
+}
+
+#[test]
+fn only_counsel_need_consent_to_upload_case_documents() {
+    let mut state = CipherState::init();
+    let (client_key, client) = identity(3);
+    let case_id = open_case(&mut state, "lawyer", Some(&client));
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+    let upload = |state: &mut CipherState, author: &str, text: &str| {
+        act_as(author);
+        let content = text.as_bytes().to_vec();
+        let doc_hash = hex::encode(Sha256::digest(&content));
+        state.upload_document_case(content, doc_hash, "text/plain".to_string(), case_id.clone())
+    };
+
+    upload(&mut state, "paralegal", "exhibit list").unwrap();
+    assert!(matches!(upload(&mut state, "lawyer", "draft motion"), Err(CipherError::ConsentRequired { scope: "write" })));
+
+    act_as(&client);
+    state.grant_consent(signed_consent(&client_key, &client, "lawyer", "write")).unwrap();
+    upload(&mut state, "lawyer", "draft motion").unwrap();
+}
+
+#[test]
+fn cases_open_in_intake_until_an_admin_activates_them() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    assert!(state.cases.get(&case_id).unwrap().unwrap().status == CaseStatus::Intake);
+
+    act_as("client");
+    assert!(matches!(
+        state.change_case_status(case_id.clone(), CaseStatus::Active),
+        Err(CipherError::Unauthorized { .. })
+    ));
+
+    act_as("lawyer");
+    assert!(matches!(
+        state.change_case_status(case_id.clone(), CaseStatus::OnHold),
+        Err(CipherError::Conflict { reason: "invalid case status transition" })
+    ));
+    state.change_case_status(case_id.clone(), CaseStatus::Active).unwrap();
+    assert!(state.cases.get(&case_id).unwrap().unwrap().status == CaseStatus::Active);
+}
+
+#[test]
+fn opposing_counsel_and_observers_do_not_receive_case_chat() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    state.add_case_member(case_id.clone(), "opposing".to_string(), CaseRole::OpposingCounsel).unwrap();
+    state.add_case_member(case_id.clone(), "observer".to_string(), CaseRole::Observer).unwrap();
+    let message_id = send(&mut state, "client", &case_id, "settlement position");
+
+    let message = state.get_message(&case_id, &message_id).unwrap().unwrap();
+    assert_eq!(message.recipient_ids, vec!["lawyer".to_string(), "client".to_string()]);
+
+    for outsider in ["opposing", "observer"] {
+        act_as(outsider);
+        assert!(matches!(
+            state.get_case_messages(case_id.clone()),
+            Err(CipherError::Unauthorized { action: "read_messages" })
+        ));
+        assert!(!state.get_unread_counts().unwrap().contains_key(&case_id));
+    }
+
+    // Nor can they be addressed directly
+    act_as("client");
+    let direct = state.send_message(
+        case_id, b"hello".to_vec(), vec![0; 12], MessageMode::Persistent, None, Some(vec!["opposing".to_string()])
+    );
+    assert!(matches!(direct, Err(CipherError::InvalidInput { field: "recipient_ids" })));
+}
+
+#[test]
+fn opposing_counsel_only_reads_documents_granted_to_them() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    state.add_case_member(case_id.clone(), "opposing".to_string(), CaseRole::OpposingCounsel).unwrap();
+    let content = b"privileged memo".to_vec();
+    let doc_hash = hex::encode(Sha256::digest(&content));
+    act_as("client");
+    state.upload_document_case(content.clone(), doc_hash.clone(), "text/plain".to_string(), case_id.clone()).unwrap();
+
+    act_as("opposing");
+    assert!(matches!(state.list_case_documents(case_id.clone()), Err(CipherError::Unauthorized { .. })));
+    assert!(matches!(
+        state.get_document_content(doc_hash.clone()),
+        Err(CipherError::Unauthorized { action: "get_document_content" })
+    ));
+
+    act_as("client");
+    state.grant_access(doc_hash.clone(), "opposing".to_string(), AccessLevel::Read, None).unwrap();
+    act_as("opposing");
+    assert_eq!(state.get_document_content(doc_hash).unwrap(), content);
+}
+
+#[test]
+fn payments_need_manage_billing() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+
+    act_as("paralegal");
+    assert!(matches!(
+        state.initiate_payment(case_id.clone(), 500),
+        Err(CipherError::Unauthorized { action: "manage_billing" })
+    ));
+
+    act_as("client");
+    let payment_id = state.initiate_payment(case_id.clone(), 500).unwrap();
+
+    act_as("paralegal");
+    assert!(matches!(state.complete_payment(payment_id.clone()), Err(CipherError::Unauthorized { .. })));
+    assert!(matches!(state.list_case_payments(case_id.clone()), Err(CipherError::Unauthorized { .. })));
+
+    act_as("client");
+    state.complete_payment(payment_id.clone()).unwrap();
+    assert!(matches!(state.complete_payment(payment_id), Err(CipherError::Conflict { .. })));
+    let payments = state.list_case_payments(case_id).unwrap();
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].status, "completed");
+}
+
+fn case_with_policy(state: &mut CipherState, policy: InvitationPolicy) -> String {
+    let case_id = open_case(state, "lawyer", Some("client"));
+    state.add_case_member(case_id.clone(), "co_counsel".to_string(), CaseRole::CoCounsel).unwrap();
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+    state.set_invitation_policy(case_id.clone(), policy).unwrap();
+    case_id
+}
+
+fn member_ids(state: &CipherState, case_id: &str) -> Vec<String> {
+    state.cases.get(case_id).unwrap().unwrap().get_participants()
+}
+
+#[test]
+fn admin_only_policy_rejects_every_other_member() {
+    let mut state = CipherState::init();
+    let case_id = case_with_policy(&mut state, InvitationPolicy::AdminOnly);
+
+    for member in ["co_counsel", "client", "paralegal"] {
+        act_as(member);
+        assert!(matches!(
+            state.add_case_member(case_id.clone(), "expert".to_string(), CaseRole::Expert),
+            Err(CipherError::Unauthorized { action: "add_case_member" })
+        ));
+    }
+
+    act_as("lawyer");
+    state.add_case_member(case_id.clone(), "expert".to_string(), CaseRole::Expert).unwrap();
+    assert!(member_ids(&state, &case_id).contains(&"expert".to_string()));
+}
+
+#[test]
+fn any_lawyer_policy_allows_co_counsel_but_not_clients() {
+    let mut state = CipherState::init();
+    let case_id = case_with_policy(&mut state, InvitationPolicy::AnyLawyer);
+
+    act_as("client");
+    assert!(matches!(
+        state.add_case_member(case_id.clone(), "expert".to_string(), CaseRole::Expert),
+        Err(CipherError::Unauthorized { action: "invite_members" })
+    ));
+    act_as("paralegal");
+    assert!(state.add_case_member(case_id.clone(), "expert".to_string(), CaseRole::Expert).is_err());
+
+    act_as("co_counsel");
+    state.add_case_member(case_id.clone(), "expert".to_string(), CaseRole::Expert).unwrap();
+    assert!(member_ids(&state, &case_id).contains(&"expert".to_string()));
+}
+
+#[test]
+fn any_member_with_approval_parks_additions_for_an_admin() {
+    let mut state = CipherState::init();
+    let case_id = case_with_policy(&mut state, InvitationPolicy::AnyMemberWithApproval);
+
+    act_as("client");
+    state.add_case_member(case_id.clone(), "expert".to_string(), CaseRole::Expert).unwrap();
+    state.add_case_member(case_id.clone(), "observer".to_string(), CaseRole::Observer).unwrap();
+    assert!(!member_ids(&state, &case_id).contains(&"expert".to_string()));
+    assert!(matches!(
+        state.add_case_member(case_id.clone(), "expert".to_string(), CaseRole::Expert),
+        Err(CipherError::Conflict { reason: "member already awaiting approval" })
+    ));
+
+    // Only an admin settles the request
+    act_as("co_counsel");
+    assert!(matches!(
+        state.approve_case_member(case_id.clone(), "expert".to_string()),
+        Err(CipherError::Unauthorized { action: "approve_case_member" })
+    ));
+
+    act_as("lawyer");
+    state.approve_case_member(case_id.clone(), "expert".to_string()).unwrap();
+    state.reject_case_member(case_id.clone(), "observer".to_string()).unwrap();
+
+    let case = state.cases.get(&case_id).unwrap().unwrap();
+    assert!(case.is_member("expert"));
+    assert!(!case.is_member("observer"));
+    assert!(case.pending_members.is_empty());
+    assert!(matches!(
+        state.approve_case_member(case_id.clone(), "observer".to_string()),
+        Err(CipherError::NotFound { kind: "pending_member", .. })
+    ));
+
+    // Outsiders cannot even request an addition
+    act_as("stranger");
+    assert!(matches!(
+        state.add_case_member(case_id, "friend".to_string(), CaseRole::Observer),
+        Err(CipherError::Unauthorized { action: "add_case_member" })
+    ));
+}
+
+fn invite(state: &mut CipherState, inviter: &str, case_id: &str, invitee: &str) -> String {
+    act_as(inviter);
+    state.invite_to_case(case_id.to_string(), invitee.to_string(), CaseRole::Expert, time_now() + 1_000_000_000)
+        .unwrap()
+}
+
+#[test]
+fn invitations_lapse_when_the_inviter_loses_the_right_to_invite() {
+    let mut state = CipherState::init();
+    let case_id = case_with_policy(&mut state, InvitationPolicy::AnyLawyer);
+    let removed = invite(&mut state, "co_counsel", &case_id, "expert");
+    let tightened = invite(&mut state, "co_counsel", &case_id, "observer");
+
+    // Tightening the policy voids invitations the inviter could no longer send
+    act_as("lawyer");
+    state.set_invitation_policy(case_id.clone(), InvitationPolicy::AdminOnly).unwrap();
+    act_as("observer");
+    assert!(matches!(
+        state.accept_invitation(tightened),
+        Err(CipherError::Conflict { reason: "inviter may no longer add members" })
+    ));
+
+    // So does removing the inviter from the case
+    act_as("lawyer");
+    state.set_invitation_policy(case_id.clone(), InvitationPolicy::AnyLawyer).unwrap();
+    state.remove_case_member(case_id.clone(), "co_counsel".to_string()).unwrap();
+    act_as("expert");
+    assert!(matches!(
+        state.accept_invitation(removed),
+        Err(CipherError::Conflict { reason: "inviter may no longer add members" })
+    ));
+    assert!(!member_ids(&state, &case_id).contains(&"expert".to_string()));
+}
+
+#[test]
+fn invitations_cannot_be_accepted_into_a_closed_case() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", None);
+    let invitation_id = invite(&mut state, "lawyer", &case_id, "expert");
+
+    act_as("lawyer");
+    state.close_case(case_id.clone()).unwrap();
+    assert!(matches!(
+        state.invite_to_case(case_id.clone(), "observer".to_string(), CaseRole::Observer, time_now() + 1_000),
+        Err(CipherError::Conflict { reason: "case is closed" })
+    ));
+
+    act_as("expert");
+    assert!(matches!(state.accept_invitation(invitation_id), Err(CipherError::Conflict { reason: "case is closed" })));
+}
+
+#[test]
+fn matter_numbers_are_sequential_per_creator() {
+    let mut state = CipherState::init();
+    // Creators whose IDs share a prefix still get distinct numbers
+    let first = open_case(&mut state, "lawyer-alice", None);
+    let second = open_case(&mut state, "lawyer-alice", None);
+    let other = open_case(&mut state, "lawyer-alfred", None);
+
+    let matter = |case_id: &str| state.cases.get(case_id).unwrap().unwrap().matter_number;
+    assert_eq!(matter(&first), "M-lawyer-alice-000001");
+    assert_eq!(matter(&second), "M-lawyer-alice-000002");
+    // The second creator's first case does not contend for the first creator's counter
+    assert_eq!(matter(&other), "M-lawyer-alfred-000001");
+}
+
+#[test]
+fn resending_the_same_payload_in_one_tick_keeps_both_messages() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+
+    // A client retry: same sender, payload and timestamp
+    let first = send(&mut state, "client", &case_id, "retry");
+    act_as("paralegal");
+    state.mark_message_read(case_id.clone(), first.clone()).unwrap();
+    let second = send(&mut state, "client", &case_id, "retry");
+    assert_ne!(first, second);
+
+    act_as("paralegal");
+    let messages = state.get_case_messages(case_id).unwrap();
+    assert_eq!(messages.iter().map(|m| m.message_id.clone()).collect::<Vec<_>>(), vec![first, second]);
+    assert!(messages[0].read_at("paralegal").is_some());
+    assert!(messages[1].read_at("paralegal").is_none());
+}
+
+#[test]
+fn concurrent_sends_on_the_same_sequence_keep_both_messages() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+
+    // Two nodes each append before seeing the other's counter update
+    let first = send(&mut state, "lawyer", &case_id, "from node a");
+    state.message_counts.insert(case_id.clone(), 0).unwrap();
+    let second = send(&mut state, "client", &case_id, "from node b");
+
+    act_as("client");
+    let messages = state.get_case_messages(case_id.clone()).unwrap();
+    let ids: Vec<_> = messages.iter().map(|m| (m.sequence, m.message_id.clone())).collect();
+    assert_eq!(ids, vec![(0, second.clone()), (0, first.clone())]);
+
+    // A counter left behind by a merge never makes a sender overwrite their own slot
+    state.message_counts.insert(case_id.clone(), 0).unwrap();
+    let third = send(&mut state, "client", &case_id, "after merge");
+    let messages = state.get_case_messages_page(case_id, None, None, 10).unwrap().messages;
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[2].message_id, third);
+    assert_eq!(messages[2].sequence, 1);
+}
+
+// Run with: cargo test --release -- --ignored --nocapture send_and_read_cost
+#[test]
+#[ignore]
+fn send_and_read_cost_is_flat_in_history_length() {
+    const SAMPLES: u32 = 100;
+    let mut costs: Vec<(u64, Duration, Duration)> = Vec::new();
+
+    for history in [100u64, 1_000, 10_000] {
+        let mut state = CipherState::init();
+        let case_id = open_case(&mut state, "lawyer", Some("client"));
+        for n in 0..history {
+            send(&mut state, "client", &case_id, &format!("message {}", n));
+            advance(1);
+        }
+
+        let started = Instant::now();
+        for n in 0..SAMPLES {
+            let message_id = send(&mut state, "client", &case_id, &format!("sample {}", n));
+            advance(1);
+            act_as("lawyer");
+            state.mark_message_read(case_id.clone(), message_id).unwrap();
+        }
+        let send_cost = started.elapsed() / SAMPLES;
+
+        act_as("client");
+        let started = Instant::now();
+        for _ in 0..SAMPLES {
+            state.get_case_messages_page(case_id.clone(), None, None, 50).unwrap();
+        }
+        let read_cost = started.elapsed() / SAMPLES;
+
+        println!("history {:>6}: send+mark {:>10?}  latest page {:>10?}", history, send_cost, read_cost);
+        costs.push((history, send_cost, read_cost));
+    }
+
+    // 100x the history must not mean anything like 100x the cost
+    let (_, small_send, small_read) = costs[0];
+    let (_, large_send, large_read) = costs[costs.len() - 1];
+    assert!(large_send < small_send * 5, "send cost grew with history");
+    assert!(large_read < small_read * 5, "read cost grew with history");
+}
+
+#[test]
+fn page_cursors_survive_the_cursor_message_expiring() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    for n in 0..5 {
+        send(&mut state, "client", &case_id, &format!("message {}", n));
+    }
+    act_as("client");
+    let short_lived = state.send_message(
+        case_id.clone(), b"gone soon".to_vec(), vec![0; 12], MessageMode::VanishAfter { seconds: 1 }, None, None
+    ).unwrap();
+    for n in 5..8 {
+        send(&mut state, "client", &case_id, &format!("message {}", n));
+    }
+
+    act_as("client");
+    let latest = state.get_case_messages_page(case_id.clone(), None, None, 3).unwrap();
+    assert!(latest.has_more);
+    assert_eq!(latest.messages.iter().map(|m| m.sequence).collect::<Vec<_>>(), vec![6, 7, 8]);
+    assert_eq!(latest.cursor, 6);
+
+    let older = state.get_case_messages_page(case_id.clone(), Some(latest.cursor), None, 1).unwrap();
+    assert_eq!(older.messages[0].message_id, short_lived);
+    assert_eq!(older.cursor, 5);
+
+    // The cursor is a sequence number, so it outlives the message it came from
+    advance(2_000_000_000);
+    let oldest = state.get_case_messages_page(case_id.clone(), Some(older.cursor), None, 10).unwrap();
+    assert!(!oldest.has_more);
+    assert_eq!(oldest.messages.iter().map(|m| m.sequence).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(oldest.cursor, 0);
+
+    let newer = state.get_case_messages_page(case_id.clone(), None, Some(5), 10).unwrap();
+    assert_eq!(newer.messages.iter().map(|m| m.sequence).collect::<Vec<_>>(), vec![6, 7, 8]);
+    assert_eq!(newer.cursor, 8);
+    let caught_up = state.get_case_messages_page(case_id, None, Some(newer.cursor), 10).unwrap();
+    assert!(caught_up.messages.is_empty());
+    assert_eq!(caught_up.cursor, 8);
+}
+
+#[test]
+fn delta_sync_finds_new_messages_appended_below_older_slots() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+    for n in 0..3 {
+        send(&mut state, "lawyer", &case_id, &format!("old {}", n));
+    }
+    advance(1_000);
+    let synced_at = time_now();
+    advance(1_000);
+
+    // A node that never saw those sends appends at slot 0; the merge then
+    // brings the other node's counter back
+    state.message_counts.insert(case_id.clone(), 0).unwrap();
+    let late = send(&mut state, "paralegal", &case_id, "from a stale node");
+    state.message_counts.insert(case_id.clone(), 3).unwrap();
+
+    act_as("client");
+    let delta = state.get_messages_since(case_id, synced_at).unwrap();
+    assert_eq!(delta.iter().map(|m| m.message_id.clone()).collect::<Vec<_>>(), vec![late]);
+}
+
+#[test]
+fn delta_sync_returns_edits_and_retractions_of_older_messages() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    let edited = send(&mut state, "client", &case_id, "draft terms");
+    let retracted = send(&mut state, "client", &case_id, "wrong attachment");
+    let untouched = send(&mut state, "client", &case_id, "see you monday");
+    advance(1_000);
+    let synced_at = time_now();
+
+    advance(1_000);
+    act_as("client");
+    state.edit_message(case_id.clone(), edited.clone(), b"final terms".to_vec(), vec![1; 12]).unwrap();
+    state.retract_message(case_id.clone(), retracted.clone()).unwrap();
+    let fresh = send(&mut state, "client", &case_id, "new message");
+
+    act_as("client");
+    let delta = state.get_messages_since(case_id.clone(), synced_at).unwrap();
+    let ids: Vec<_> = delta.iter().map(|m| m.message_id.clone()).collect();
+    assert_eq!(ids, vec![edited.clone(), retracted, fresh]);
+    assert!(!ids.contains(&untouched));
+    assert_eq!(delta[0].ciphertext, b"final terms".to_vec());
+    assert!(delta[1].retracted_at.is_some());
+
+    // A second edit is reported once, with the latest content
+    advance(1_000);
+    let synced_at = time_now();
+    advance(1_000);
+    state.edit_message(case_id.clone(), edited.clone(), b"final terms v2".to_vec(), vec![2; 12]).unwrap();
+    let delta = state.get_messages_since(case_id, synced_at).unwrap();
+    assert_eq!(delta.len(), 1);
+    assert_eq!(delta[0].ciphertext, b"final terms v2".to_vec());
+}
+
+#[test]
+fn replies_stay_within_the_audience_of_their_parent() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+    state.add_case_member(case_id.clone(), "expert".to_string(), CaseRole::Expert).unwrap();
+
+    act_as("client");
+    let parent = state.send_message(
+        case_id.clone(), b"just between us".to_vec(), vec![0; 12], MessageMode::Persistent,
+        None, Some(vec!["paralegal".to_string()])
+    ).unwrap();
+
+    act_as("paralegal");
+    let reply = state.send_message(
+        case_id.clone(), b"understood".to_vec(), vec![0; 12], MessageMode::Persistent, Some(parent.clone()), None
+    ).unwrap();
+    let reply = state.get_message(&case_id, &reply).unwrap().unwrap();
+    assert_eq!(reply.recipient_ids, vec!["client".to_string(), "paralegal".to_string()]);
+    assert_eq!(reply.thread_root, Some(parent));
+
+    act_as("expert");
+    assert!(state.get_case_messages(case_id.clone()).unwrap().is_empty());
+
+    // An explicit audience still overrides the inherited one
+    act_as("paralegal");
+    let widened = state.send_message(
+        case_id.clone(), b"looping in the expert".to_vec(), vec![0; 12], MessageMode::Persistent,
+        Some(reply.message_id), Some(vec!["expert".to_string()])
+    ).unwrap();
+    act_as("expert");
+    let visible: Vec<_> = state.get_case_messages(case_id).unwrap().into_iter().map(|m| m.message_id).collect();
+    assert_eq!(visible, vec![widened]);
+}
+
+#[test]
+fn unread_counts_start_from_the_members_read_mark() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    for n in 0..3 {
+        send(&mut state, "lawyer", &case_id, &format!("before joining {}", n));
+    }
+    advance(1_000);
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+    let mark = |state: &CipherState| state.read_marks.get(&read_mark_key(&case_id, "paralegal", "lawyer")).unwrap();
+    assert_eq!(mark(&state), Some(3));
+
+    let first = send(&mut state, "lawyer", &case_id, "first");
+    let second = send(&mut state, "lawyer", &case_id, "second");
+    send(&mut state, "paralegal", &case_id, "own message");
+    act_as("paralegal");
+    assert_eq!(state.get_unread_counts().unwrap()[&case_id], 2);
+
+    // Reading out of order leaves the mark on the oldest unread message
+    state.mark_message_read(case_id.clone(), second).unwrap();
+    assert_eq!(state.get_unread_counts().unwrap()[&case_id], 1);
+    assert_eq!(mark(&state), Some(3));
+
+    state.mark_message_read(case_id.clone(), first).unwrap();
+    assert_eq!(state.get_unread_counts().unwrap()[&case_id], 0);
+    assert_eq!(mark(&state), Some(5));
+}
+
+#[test]
+fn unread_counts_include_messages_merged_into_slots_behind_a_read_mark() {
+    let mut state = CipherState::init();
+    let case_id = open_case(&mut state, "lawyer", Some("client"));
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+    let read = send(&mut state, "lawyer", &case_id, "already read");
+    act_as("paralegal");
+    state.mark_message_read(case_id.clone(), read).unwrap();
+
+    // A node that never saw the lawyer's message sends into slot 0
+    state.message_counts.insert(case_id.clone(), 0).unwrap();
+    let late = send(&mut state, "client", &case_id, "from a stale node");
+    assert_eq!(state.get_message(&case_id, &late).unwrap().unwrap().sequence, 0);
+
+    act_as("paralegal");
+    assert_eq!(state.get_unread_counts().unwrap()[&case_id], 1);
+    state.mark_message_read(case_id.clone(), late).unwrap();
+    assert_eq!(state.get_unread_counts().unwrap()[&case_id], 0);
+}
+
+#[test]
+fn messaging_requires_a_current_case_membership() {
+    let mut state = CipherState::init();
+    let (client_key, client) = identity(2);
+    let case_id = open_case(&mut state, "admin", Some(&client));
+    state.add_case_member(case_id.clone(), "associate".to_string(), CaseRole::Lawyer).unwrap();
+    state.add_case_member(case_id.clone(), "removed".to_string(), CaseRole::Paralegal).unwrap();
+    act_as(&client);
+    for counsel in ["admin", "associate"] {
+        state.grant_consent(signed_consent(&client_key, &client, counsel, "read")).unwrap();
+    }
+
+    // Members
+    for member in ["admin", "associate", client.as_str()] {
+        let incoming = send(&mut state, "removed", &case_id, &format!("for {}", member));
+        act_as(member);
+        state.send_message(case_id.clone(), b"hello".to_vec(), vec![0; 12], MessageMode::Persistent, None, None)
+            .unwrap();
+        assert!(state.get_case_messages(case_id.clone()).unwrap().iter().any(|m| m.message_id == incoming));
+        state.mark_message_read(case_id.clone(), incoming).unwrap();
+    }
+
+    // A removed member and an outsider, even with a message addressed to one of them
+    let addressed = send(&mut state, "admin", &case_id, "before removal");
+    act_as("admin");
+    state.remove_case_member(case_id.clone(), "removed".to_string()).unwrap();
+
+    for outsider in ["removed", "outsider"] {
+        act_as(outsider);
+        assert!(matches!(
+            state.send_message(case_id.clone(), b"hello".to_vec(), vec![0; 12], MessageMode::Persistent, None, None),
+            Err(CipherError::Unauthorized { action: "send_message" })
+        ));
+        assert!(matches!(
+            state.get_case_messages(case_id.clone()),
+            Err(CipherError::Unauthorized { action: "read_messages" })
+        ));
+        assert!(matches!(
+            state.get_case_messages_page(case_id.clone(), None, None, 10),
+            Err(CipherError::Unauthorized { action: "read_messages" })
+        ));
+        assert!(matches!(
+            state.mark_message_read(case_id.clone(), addressed.clone()),
+            Err(CipherError::Unauthorized { action: "mark_message_read" })
+        ));
+    }
+}
+
+fn begin_upload(state: &mut CipherState, owner: &str, content: &[u8]) -> String {
+    act_as(owner);
+    let doc_hash = hex::encode(Sha256::digest(content));
+    state.begin_upload(doc_hash, "application/pdf".to_string(), content.len() as u64, None, None, None).unwrap()
+}
+
+#[test]
+fn chunked_uploads_assemble_and_stream_back() {
+    let mut state = CipherState::init();
+    let content: Vec<u8> = (0..MAX_CHUNK_SIZE * 2 + 10).map(|n| n as u8).collect();
+    let upload_id = begin_upload(&mut state, "alice", &content);
+
+    // Chunks may arrive out of order
+    for (index, chunk) in content.chunks(MAX_CHUNK_SIZE).enumerate().rev() {
+        state.upload_chunk(upload_id.clone(), index as u32, chunk.to_vec()).unwrap();
+    }
+    let doc_hash = state.finalize_upload(upload_id).unwrap();
+
+    let mut streamed = Vec::new();
+    for index in 0..3 {
+        let chunk = state.get_document_chunk(doc_hash.clone(), index).unwrap();
+        assert_eq!(chunk.chunk_count, 3);
+        streamed.extend(chunk.data);
+    }
+    assert_eq!(streamed, content);
+}
+
+#[test]
+fn idle_uploads_expire_and_are_purged_with_their_chunks() {
+    let mut state = CipherState::init();
+    let content = vec![7u8; MAX_CHUNK_SIZE + 1];
+    let upload_id = begin_upload(&mut state, "alice", &content);
+    let chunk_hash = state.upload_chunk(upload_id.clone(), 0, content[..MAX_CHUNK_SIZE].to_vec()).unwrap();
+
+    // Every chunk pushes the deadline back
+    advance(UPLOAD_IDLE_TIMEOUT - 1);
+    state.upload_chunk(upload_id.clone(), 1, content[MAX_CHUNK_SIZE..].to_vec()).unwrap();
+    advance(UPLOAD_IDLE_TIMEOUT);
+    assert!(matches!(state.finalize_upload(upload_id.clone()), Err(CipherError::Expired { what: "upload" })));
+
+    act_as("bob");
+    assert_eq!(state.purge_expired_uploads().unwrap(), 1);
+    assert!(state.uploads.get(&upload_id).unwrap().is_none());
+    assert!(state.chunks.get(&chunk_hash).unwrap().is_none());
+}
+
+#[test]
+fn aborting_an_upload_keeps_chunks_other_documents_use() {
+    let mut state = CipherState::init();
+    let shared = vec![1u8; MAX_CHUNK_SIZE];
+    let stored = store_vault_document(&mut state, "alice", "x");
+    let mut stored_doc = state.documents.get(&stored).unwrap().unwrap();
+
+    let mut content = shared.clone();
+    content.extend_from_slice(b"tail");
+    let upload_id = begin_upload(&mut state, "alice", &content);
+    let shared_hash = state.upload_chunk(upload_id.clone(), 0, shared).unwrap();
+    let tail_hash = state.upload_chunk(upload_id.clone(), 1, b"tail".to_vec()).unwrap();
+
+    // Pretend an existing document already holds the first chunk
+    stored_doc.chunk_hashes.push(shared_hash.clone());
+    state.documents.insert(stored, stored_doc).unwrap();
+
+    act_as("bob");
+    assert!(matches!(state.abort_upload(upload_id.clone()), Err(CipherError::Unauthorized { action: "abort_upload" })));
+    act_as("alice");
+    state.abort_upload(upload_id.clone()).unwrap();
+
+    assert!(state.uploads.get(&upload_id).unwrap().is_none());
+    assert!(state.chunks.get(&shared_hash).unwrap().is_some());
+    assert!(state.chunks.get(&tail_hash).unwrap().is_none());
+}
+
+#[test]
+fn case_listings_only_show_the_cases_own_readable_documents() {
+    let mut state = CipherState::init();
+    let foreign = store_vault_document(&mut state, "alice", "alice's will");
+    act_as("lawyer");
+    let case_id = state.open_case(CaseCreateParams {
+        case_name: "Doe v. Roe".to_string(),
+        description: "Contract dispute".to_string(),
+        client_id: Some("client".to_string()),
+        privacy_level: PrivacyLevel::Private,
+        initial_docs: Some(vec![foreign.clone()]),
+        invitation_policy: None,
+    }).unwrap();
+    state.add_case_member(case_id.clone(), "paralegal".to_string(), CaseRole::Paralegal).unwrap();
+
+    let content = b"engagement letter".to_vec();
+    let doc_hash = hex::encode(Sha256::digest(&content));
+    act_as("client");
+    state.upload_document_case(content, doc_hash.clone(), "application/pdf".to_string(), case_id.clone()).unwrap();
+    state.grant_access(doc_hash.clone(), "expert".to_string(), AccessLevel::Read, None).unwrap();
+
+    let listed = state.list_case_documents(case_id.clone()).unwrap();
+    assert_eq!(listed.iter().map(|doc| doc.document_hash.clone()).collect::<Vec<_>>(), vec![doc_hash.clone()]);
+    assert_eq!(listed[0].grants.len(), 1);
+
+    // Grants are the owner's business
+    act_as("paralegal");
+    let listed = state.list_case_documents(case_id).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(listed[0].grants.is_empty());
+}
+
+#[test]
+fn analysis_status_follows_the_documents_analyses() {
+    let mut state = CipherState::init();
+    let doc_hash = store_vault_document(&mut state, "alice", "lease");
+    let status = |state: &CipherState| {
+        act_as("alice");
+        let summary = state.get_accessible_documents().unwrap().into_iter().next().unwrap();
+        (summary.analysis_status, summary.analysis_id)
+    };
+    assert!(status(&state) == (AnalysisStatus::NotRequested, None));
+
+    act_as("alice");
+    let analysis_id = state.request_ai_analysis(doc_hash.clone(), "ai".to_string()).unwrap();
+    assert!(status(&state) == (AnalysisStatus::Pending, Some(analysis_id.clone())));
+
+    advance(1_000);
+    act_as("ai");
+    // An empty summary is still a finished analysis
+    state.update_ai_analysis(analysis_id.clone(), String::new(), 0, Vec::new()).unwrap();
+    assert!(status(&state) == (AnalysisStatus::Completed, Some(analysis_id)));
+}
+
+#[test]
+fn delegated_grants_are_capped_by_the_granters_own_grant() {
+    let mut state = CipherState::init();
+    let doc_hash = store_vault_document(&mut state, "alice", "title deed");
+    let bob_expiry = time_now() + 10_000;
+    state.grant_access(doc_hash.clone(), "bob".to_string(), AccessLevel::Share, Some(bob_expiry)).unwrap();
+    state.grant_access(doc_hash.clone(), "dave".to_string(), AccessLevel::Read, None).unwrap();
+    let grant_of = |state: &CipherState, grantee: &str| {
+        state.documents.get(&doc_hash).unwrap().unwrap().grants.into_iter()
+            .find(|grant| grant.grantee_id == grantee)
+            .unwrap()
+    };
+
+    act_as("bob");
+    state.grant_access(doc_hash.clone(), "carol".to_string(), AccessLevel::Read, None).unwrap();
+    assert_eq!(grant_of(&state, "carol").expires_at, Some(bob_expiry));
+    state.grant_access(doc_hash.clone(), "carol".to_string(), AccessLevel::Read, Some(bob_expiry + 5_000)).unwrap();
+    assert_eq!(grant_of(&state, "carol").expires_at, Some(bob_expiry));
+    state.grant_access(doc_hash.clone(), "carol".to_string(), AccessLevel::Read, Some(bob_expiry - 5_000)).unwrap();
+    assert_eq!(grant_of(&state, "carol").expires_at, Some(bob_expiry - 5_000));
+
+    // Dave's grant came from the owner, so bob cannot touch it
+    assert!(matches!(
+        state.grant_access(doc_hash.clone(), "dave".to_string(), AccessLevel::Share, None),
+        Err(CipherError::Unauthorized { action: "grant_access" })
+    ));
+    assert!(grant_of(&state, "dave").level == AccessLevel::Read);
+
+    // Carol's read grant gives her nothing to delegate
+    act_as("carol");
+    assert!(matches!(
+        state.grant_access(doc_hash.clone(), "erin".to_string(), AccessLevel::Read, None),
+        Err(CipherError::Unauthorized { .. })
+    ));
+    act_as("alice");
+    state.grant_access(doc_hash.clone(), "dave".to_string(), AccessLevel::Write, None).unwrap();
+    assert!(grant_of(&state, "dave").level == AccessLevel::Write);
+}
+
+#[test]
+fn revoking_a_delegator_voids_the_grants_they_issued() {
+    let mut state = CipherState::init();
+    let doc_hash = store_vault_document(&mut state, "alice", "share register");
+    state.grant_access(doc_hash.clone(), "bob".to_string(), AccessLevel::Share, None).unwrap();
+    state.grant_access(doc_hash.clone(), "erin".to_string(), AccessLevel::Read, Some(time_now() + 10)).unwrap();
+    act_as("bob");
+    state.grant_access(doc_hash.clone(), "carol".to_string(), AccessLevel::Share, None).unwrap();
+    act_as("carol");
+    state.grant_access(doc_hash.clone(), "dave".to_string(), AccessLevel::Read, None).unwrap();
+    let readable = |state: &CipherState, who: &str| {
+        act_as(who);
+        state.get_document_content(doc_hash.clone()).is_ok()
+    };
+    assert!(readable(&state, "carol") && readable(&state, "dave"));
+
+    advance(1_000);
+    act_as("alice");
+    state.revoke_access(doc_hash.clone(), "bob".to_string()).unwrap();
+    assert!(!readable(&state, "carol") && !readable(&state, "dave"));
+    act_as("alice");
+    assert!(state.get_accessible_documents().unwrap()[0].grants.is_empty());
+
+    // Taking bob back on does not revive what he handed out before
+    state.grant_access(doc_hash.clone(), "bob".to_string(), AccessLevel::Share, None).unwrap();
+    assert!(readable(&state, "bob"));
+    assert!(!readable(&state, "carol") && !readable(&state, "dave"));
+
+    // Expired and voided grants stay on record
+    let grantees: Vec<_> = state.documents.get(&doc_hash).unwrap().unwrap().grants.into_iter()
+        .map(|grant| grant.grantee_id)
+        .collect();
+    assert_eq!(grantees, vec!["erin", "carol", "dave", "bob"]);
+}